@@ -2,32 +2,60 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use dotenv::dotenv;
 use hashbrown::HashMap;
 use mole::{
-    cli::Args,
+    cli::{Args, FilterMode, GroupBy, Mode, DEFAULT_SKIP_DIRS},
     file_explorer::{self, CargoFiles},
+    parser::data::OutputRow,
     printer::PrintFormat,
 };
 use std::env;
 use std::{hint::black_box, path::PathBuf};
 
-fn search(args: &Args) -> Result<HashMap<String, CargoFiles>, mole::error::MoleError> {
-    file_explorer::explore(&args)
+fn explore(args: &Args) -> Result<HashMap<String, CargoFiles>, mole::error::MoleError> {
+    file_explorer::explore(args)
+}
+
+fn search(args: &Args) -> Result<Vec<OutputRow>, mole::error::MoleError> {
+    mole::search(args)
+}
+
+fn args(path: PathBuf, threaded: bool) -> Args {
+    Args {
+        name: vec!["serde".to_string()],
+        regex: Vec::new(),
+        path,
+        source: Vec::new(),
+        feature: Vec::new(),
+        target: Vec::new(),
+        mode: Mode::Search,
+        all_paths: false,
+        group_by: GroupBy::Project,
+        deny_duplicates: false,
+        deny_stale: false,
+        deep: true,
+        threaded,
+        threads: None,
+        filter: Some(">=1.0.0".to_string()),
+        filter_mode: FilterMode::Intersects,
+        output: PrintFormat::Table,
+        symlinks: false,
+        no_ignore: false,
+        exclude: Vec::new(),
+        include: Vec::new(),
+        max_depth: None,
+        skip_dirs: DEFAULT_SKIP_DIRS.map(String::from).to_vec(),
+    }
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
     dotenv().ok();
     let path = PathBuf::from(env::var("DEFAULT_BENCHES_FOLDER").unwrap());
-    c.bench_function("search", |b| {
-        b.iter(|| {
-            search(black_box(&Args {
-                name: "serde".to_string(),
-                path: path.clone(),
-                deep: true,
-                threaded: true,
-                filter: None,
-                output: PrintFormat::Table,
-                symlinks: false,
-            }))
-        })
+    let threaded = args(path.clone(), true);
+    let sequential = args(path, false);
+
+    c.bench_function("explore", |b| b.iter(|| explore(black_box(&threaded))));
+    c.bench_function("search", |b| b.iter(|| search(black_box(&threaded))));
+    c.bench_function("search sequential", |b| {
+        b.iter(|| search(black_box(&sequential)))
     });
 }
 
@@ -0,0 +1,351 @@
+use crate::{
+    file_explorer::absolute,
+    parser::{
+        data::OutputRow,
+        source::{normalize_index, Source},
+    },
+};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// The name cargo gives to the crates.io source in `[source]` tables.
+const CRATES_IO: &str = "crates-io";
+/// Config files read in each directory, cargo preferring the legacy `config` when both exist.
+const CONFIG_FILES: [&str; 2] = ["config", "config.toml"];
+/// Bound on `replace-with` chains, which cargo rejects when they form a cycle.
+const MAX_REPLACEMENTS: usize = 16;
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    registries: Option<HashMap<String, RegistryConfig>>,
+    source: Option<HashMap<String, SourceConfig>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RegistryConfig {
+    index: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct SourceConfig {
+    /// The index URL of a source defined as a registry.
+    registry: Option<String>,
+    replace_with: Option<String>,
+}
+
+/// The registry a dependency comes from, named as in the `.cargo/config.toml` files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Registry {
+    pub name: String,
+    /// The source cargo downloads the crate from instead, e.g. a vendored directory
+    /// or a mirror configured with `replace-with`.
+    pub replaced_with: Option<String>,
+}
+
+/// The `[registries]` and `[source]` settings in effect for a project, merged the way
+/// cargo does: config files closer to the project take precedence, and the one in
+/// `$CARGO_HOME` comes last.
+#[derive(Debug, Default)]
+pub struct CargoConfig {
+    /// Index URL of every named registry.
+    registries: HashMap<String, String>,
+    sources: HashMap<String, SourceConfig>,
+}
+
+/// Loads the config files applying to each project, every file being read once however
+/// many projects it applies to.
+#[derive(Debug, Default)]
+pub struct ConfigLoader {
+    /// The config file of every `.cargo` directory looked at, `None` when it has none.
+    files: Mutex<HashMap<PathBuf, Option<Arc<ConfigFile>>>>,
+}
+
+impl ConfigLoader {
+    /// Loads the config files applying to the project in the given directory.
+    pub fn load(&self, project_dir: &Path) -> CargoConfig {
+        self.load_with_home(project_dir, cargo_home())
+    }
+
+    fn load_with_home(&self, project_dir: &Path, cargo_home: Option<PathBuf>) -> CargoConfig {
+        let project_dir = absolute(project_dir);
+        let mut config = CargoConfig::default();
+        let dirs = project_dir
+            .ancestors()
+            .map(|dir| dir.join(".cargo"))
+            .chain(cargo_home);
+        for file in dirs.filter_map(|dir| self.read(dir)) {
+            config.merge(&file);
+        }
+        config
+    }
+
+    fn read(&self, dir: PathBuf) -> Option<Arc<ConfigFile>> {
+        let mut files = self
+            .files
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        files
+            .entry(dir)
+            .or_insert_with_key(|dir| read_config(dir).map(Arc::new))
+            .clone()
+    }
+}
+
+/// Reads the config file of a `.cargo` directory, if any.
+fn read_config(dir: &Path) -> Option<ConfigFile> {
+    let file = CONFIG_FILES
+        .iter()
+        .map(|file| dir.join(file))
+        .find(|file| file.is_file())?;
+    match fs::read_to_string(&file).map(|contents| toml::from_str(&contents)) {
+        Ok(Ok(parsed)) => Some(parsed),
+        Ok(Err(e)) => {
+            eprintln!("Unparseable file: {:?} {e}", file);
+            None
+        }
+        Err(e) => {
+            eprintln!("Error reading file: {} | {e}", file.display());
+            None
+        }
+    }
+}
+
+impl CargoConfig {
+    /// Adds the settings of a config file with a lower precedence than the ones read so far.
+    fn merge(&mut self, file: &ConfigFile) {
+        for (name, registry) in file.registries.iter().flatten() {
+            if let Some(index) = &registry.index {
+                self.registries
+                    .entry(name.clone())
+                    .or_insert_with(|| index.clone());
+            }
+        }
+        for (name, source) in file.source.iter().flatten() {
+            self.sources
+                .entry(name.clone())
+                .or_insert_with(|| source.clone());
+        }
+    }
+
+    /// Names the registry of every row coming from one, filling in the name or the index
+    /// URL of alternate registries on the way.
+    pub fn label(&self, rows: &mut [OutputRow]) {
+        for row in rows {
+            let registry = row.source.as_mut().and_then(|source| self.registry(source));
+            let locked_registry = row
+                .locked
+                .as_mut()
+                .and_then(|locked| locked.source.as_mut())
+                .and_then(|source| self.registry(source));
+            // Dependencies whose workspace could not be found are only known by their lock.
+            row.registry = registry.or(locked_registry);
+        }
+    }
+
+    /// The registry a source belongs to, completing the name or index it lacks.
+    fn registry(&self, source: &mut Source) -> Option<Registry> {
+        let name = match source {
+            Source::CratesIo => CRATES_IO.to_string(),
+            Source::Registry {
+                name: Some(name),
+                index,
+            } => {
+                if index.is_none() {
+                    *index = self.registries.get(name).cloned();
+                }
+                name.clone()
+            }
+            Source::Registry {
+                name,
+                index: Some(index),
+            } => {
+                let found = self.registry_name(index)?;
+                *name = Some(found.clone());
+                found
+            }
+            _ => return None,
+        };
+
+        Some(Registry {
+            replaced_with: self.replacement(&name),
+            name,
+        })
+    }
+
+    /// The name under which a registry index is configured.
+    fn registry_name(&self, index: &str) -> Option<String> {
+        let index = normalize_index(index);
+        self.registries
+            .iter()
+            .find(|(_, candidate)| normalize_index(candidate) == index)
+            .or_else(|| {
+                self.sources.iter().find_map(|(name, source)| {
+                    source
+                        .registry
+                        .as_ref()
+                        .filter(|candidate| normalize_index(candidate) == index)
+                        .map(|candidate| (name, candidate))
+                })
+            })
+            .map(|(name, _)| name.clone())
+    }
+
+    /// The source that finally replaces the given one, following `replace-with` chains.
+    fn replacement(&self, source: &str) -> Option<String> {
+        let mut replaced = None;
+        let mut current = source;
+        for _ in 0..MAX_REPLACEMENTS {
+            let Some(next) = self
+                .sources
+                .get(current)
+                .and_then(|source| source.replace_with.as_deref())
+            else {
+                break;
+            };
+            replaced = Some(next.to_string());
+            current = next;
+        }
+        replaced
+    }
+}
+
+/// `$CARGO_HOME`, defaulting to `~/.cargo`.
+fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CargoConfig, ConfigLoader, Registry};
+    use crate::{fixture, parser::source::Source};
+    use std::fs;
+
+    #[test]
+    fn name_registries() {
+        let mut config = CargoConfig::default();
+        config.merge(
+            &toml::from_str(
+                r#"
+                [registries.internal]
+                index = "sparse+https://internal.example/index/"
+
+                [source.crates-io]
+                replace-with = "mirror"
+
+                [source.mirror]
+                registry = "sparse+https://mirror.example/index"
+                "#,
+            )
+            .unwrap(),
+        );
+        config.merge(
+            &toml::from_str(
+                r#"
+                [registries.internal]
+                index = "https://ignored.example/index"
+                "#,
+            )
+            .unwrap(),
+        );
+
+        let mut locked = Source::Registry {
+            name: None,
+            index: Some("sparse+https://internal.example/index".to_string()),
+        };
+        assert_eq!(
+            config.registry(&mut locked),
+            Some(Registry {
+                name: "internal".to_string(),
+                replaced_with: None
+            })
+        );
+        assert_eq!(locked.to_string(), "registry internal");
+
+        let mut declared = Source::Registry {
+            name: Some("internal".to_string()),
+            index: None,
+        };
+        config.registry(&mut declared);
+        assert_eq!(
+            declared,
+            Source::Registry {
+                name: Some("internal".to_string()),
+                index: Some("sparse+https://internal.example/index/".to_string())
+            }
+        );
+
+        assert_eq!(
+            config.registry(&mut Source::CratesIo),
+            Some(Registry {
+                name: "crates-io".to_string(),
+                replaced_with: Some("mirror".to_string())
+            })
+        );
+        assert_eq!(config.registry(&mut Source::Path { path: None }), None);
+    }
+
+    #[test]
+    fn load_config_files() {
+        let dir = fixture::dir(&[
+            (
+                "workspace/.cargo/config.toml",
+                r#"
+                [registries.inner]
+                index = "https://outer.example/inner"
+
+                [registries.outer]
+                index = "https://outer.example/index"
+                "#,
+            ),
+            // The legacy `config` is read instead of `config.toml` in the same directory.
+            (
+                "workspace/project/.cargo/config",
+                r#"
+                [registries.inner]
+                index = "https://inner.example/index"
+                "#,
+            ),
+            (
+                "workspace/project/.cargo/config.toml",
+                r#"
+                [registries.ignored]
+                index = "https://ignored.example/index"
+                "#,
+            ),
+            (
+                "home/config.toml",
+                r#"
+                [registries.outer]
+                index = "https://home.example/outer"
+
+                [registries.home]
+                index = "https://home.example/index"
+                "#,
+            ),
+        ]);
+
+        let loader = ConfigLoader::default();
+        let project = dir.path().join("workspace/project");
+        let home = dir.path().join("home");
+        let config = loader.load_with_home(&project, Some(home.clone()));
+
+        let index = |name: &str| config.registries.get(name).map(String::as_str);
+        assert_eq!(index("inner"), Some("https://inner.example/index"));
+        assert_eq!(index("outer"), Some("https://outer.example/index"));
+        assert_eq!(index("home"), Some("https://home.example/index"));
+        assert_eq!(index("ignored"), None);
+
+        // Files already read are not read again.
+        fs::remove_dir_all(dir.path()).unwrap();
+        let cached = loader.load_with_home(&project, Some(home));
+        assert_eq!(cached.registries, config.registries);
+    }
+}
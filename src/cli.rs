@@ -1,13 +1,106 @@
-use crate::printer::PrintFormat;
-use clap::Parser;
-use std::path::PathBuf;
+use crate::{
+    error::MoleError,
+    matcher::DependencyMatcher,
+    parser::{cfg::TargetInfo, data::OutputRow, source::SourceKind},
+    printer::PrintFormat,
+};
+use clap::{Parser, ValueEnum};
+use std::{num::NonZeroUsize, path::PathBuf};
 
-/// Searches recursively for a specified cargo dependency in all projects within a given directory.
-#[derive(Parser, Debug)]
+/// Directories that hold build outputs or toolchain caches rather than projects.
+pub const DEFAULT_SKIP_DIRS: [&str; 3] = ["target", ".cargo", ".rustup"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Mode {
+    /// Report where the dependency is declared and locked.
+    Search,
+    /// Print the chain of locked dependencies leading from each local crate to the dependency.
+    Why,
+    /// List the crates locked at more than one version in each Cargo.lock.
+    Duplicates,
+    /// List the dependencies whose locked version does not satisfy their requirement.
+    /// The names, --filter, --source and --feature narrow the dependencies checked.
+    Stale,
+    /// List the projects overriding the dependency through `[patch]` or `[replace]`.
+    Patches,
+}
+
+impl Mode {
+    /// Whether the mode works on Cargo.lock files, which are then always collected.
+    pub fn needs_lock(&self) -> bool {
+        matches!(self, Mode::Why | Mode::Duplicates | Mode::Stale)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// One entry per project and duplicated crate.
+    Project,
+    /// One entry per duplicated crate, listing every project that duplicates it.
+    Crate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FilterMode {
+    /// Keep requirements that could resolve to a version matching the filter.
+    Intersects,
+    /// Keep requirements whose every version matches the filter.
+    Subset,
+}
+
+impl Args {
+    /// The crates the search is about, built from the names and regular expressions given.
+    pub fn matcher(&self) -> Result<DependencyMatcher, MoleError> {
+        DependencyMatcher::new(&self.name, &self.regex)
+    }
+
+    /// Same as [`Args::matcher`], failing when no name nor pattern was given, which `why`
+    /// mode requires.
+    pub fn required_matcher(&self) -> Result<DependencyMatcher, MoleError> {
+        let matcher = self.matcher()?;
+        if matcher.is_empty() {
+            return Err(MoleError::MissingName);
+        }
+        Ok(matcher)
+    }
+
+    /// The platforms given with `--target`.
+    pub fn targets(&self) -> Vec<TargetInfo> {
+        self.target
+            .iter()
+            .map(|triple| TargetInfo::from_triple(triple))
+            .collect()
+    }
+
+    /// Whether a result is kept by the `--source` and `--feature` filters. Dependencies
+    /// inheriting from a workspace that could not be found have no known source and are
+    /// kept by any source filter.
+    pub fn keeps(&self, row: &OutputRow) -> bool {
+        let source = self.source.is_empty()
+            || row
+                .source
+                .as_ref()
+                .is_none_or(|source| self.source.contains(&source.kind()));
+        source && self.feature.iter().all(|feature| row.enables(feature))
+    }
+}
+
+/// Searches recursively for a specified cargo dependency in all projects within a given directory,
+/// or lists every dependency when none is given.
+#[derive(Parser, Debug, Clone)]
 #[command(version, about, long_about = None)]
 pub struct Args {
-    /// The name of the dependency to search for.
-    pub name: String,
+    /// The names of the dependencies to search for, which may be glob patterns such as "tokio-*".
+    /// When omitted, `search` mode lists every dependency of every project, while `duplicates`,
+    /// `stale` and `patches` modes check every crate.
+    pub name: Vec<String>,
+
+    /// Regular expression matching the whole name of the dependencies to search for.
+    /// Can be repeated.
+    ///
+    /// Example: "aws-sdk-.*"
+    #[arg(short, long)]
+    pub regex: Vec<String>,
 
     /// The directory to search in.
     #[arg(short, long, default_value = ".")]
@@ -20,7 +113,53 @@ pub struct Args {
     #[arg(short, long, default_value = None)]
     pub filter: Option<String>,
 
-    /// Output format.
+    /// How version requirements from Cargo.toml are compared to the filter.
+    /// Versions from Cargo.lock are exact and simply have to match it.
+    #[arg(long, default_value = "intersects")]
+    pub filter_mode: FilterMode,
+
+    /// Only report dependencies coming from this kind of source. Can be repeated.
+    /// Dependencies inheriting from a workspace that could not be found are always reported.
+    #[arg(long, value_delimiter = ',')]
+    pub source: Vec<SourceKind>,
+
+    /// Only report dependencies declared with this feature enabled, either "feature" or
+    /// "crate/feature". Can be repeated, every feature being then required.
+    ///
+    /// Example: "tokio/full"
+    #[arg(long)]
+    pub feature: Vec<String>,
+
+    /// Target triple to evaluate the `[target.'cfg(...)']` tables against, reporting whether
+    /// each dependency applies to it. Can be repeated. Only common architectures and
+    /// operating systems are known, others being warned about.
+    ///
+    /// Example: "x86_64-unknown-linux-musl"
+    #[arg(long)]
+    pub target: Vec<String>,
+
+    /// What to report about the dependency.
+    #[arg(short, long, default_value = "search")]
+    pub mode: Mode,
+
+    /// Flag to indicate whether `why` mode prints every dependency chain instead of the shortest one.
+    #[arg(short, long, default_value_t = false)]
+    pub all_paths: bool,
+
+    /// How `duplicates` mode groups its report.
+    #[arg(short, long, default_value = "project")]
+    pub group_by: GroupBy,
+
+    /// Flag to indicate whether `duplicates` mode exits with a failure when duplicates are found.
+    #[arg(long, default_value_t = false)]
+    pub deny_duplicates: bool,
+
+    /// Flag to indicate whether `stale` mode exits with a failure when stale locks are found.
+    #[arg(long, default_value_t = false)]
+    pub deny_stale: bool,
+
+    /// Output format. Tables group the results by workspace, while JSON keys them by package,
+    /// each entry naming its `workspace`.
     #[arg(short, long, default_value = "table")]
     pub output: PrintFormat,
 
@@ -32,7 +171,39 @@ pub struct Args {
     #[arg(short, long, default_value_t = false)]
     pub threaded: bool,
 
+    /// Number of threads used when exploring in parallel.
+    /// Defaults to the available parallelism of the machine.
+    #[arg(short = 'j', long, default_value = None)]
+    pub threads: Option<NonZeroUsize>,
+
     /// Flag to indicate whether to follow symbolic links.
     #[arg(short, long, default_value_t = false)]
     pub symlinks: bool,
+
+    /// Flag to disable `.gitignore` and `.ignore` files, which are honoured by default.
+    /// The `why` mode reads Cargo.lock files even when they are ignored.
+    #[arg(short, long, default_value_t = false)]
+    pub no_ignore: bool,
+
+    /// Glob of paths to skip, relative to the search directory. Can be repeated.
+    ///
+    /// Example: "vendor/**"
+    #[arg(short, long)]
+    pub exclude: Vec<String>,
+
+    /// Glob of paths to read, relative to the search directory. Can be repeated.
+    /// When given, only cargo files matching at least one of them are read.
+    ///
+    /// Example: "services/**"
+    #[arg(short, long)]
+    pub include: Vec<String>,
+
+    /// Maximum depth to descend to, the direct entries of the search directory being at depth 1.
+    #[arg(long, default_value = None)]
+    pub max_depth: Option<usize>,
+
+    /// Names of directories that are never descended into, coma separated.
+    /// Pass an empty value to descend into every directory.
+    #[arg(long, value_delimiter = ',', default_values_t = DEFAULT_SKIP_DIRS.map(String::from))]
+    pub skip_dirs: Vec<String>,
 }
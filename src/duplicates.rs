@@ -0,0 +1,168 @@
+use crate::{
+    cli::Args,
+    error::MoleError,
+    file_explorer,
+    parser::{data::CLockFile, source::Source, FileParser, DEFAULT_PACKAGE_NAME},
+};
+use hashbrown::HashMap;
+use semver::Version;
+use std::fs;
+
+/// A crate locked at more than one version in a Cargo.lock.
+#[derive(Debug, Clone)]
+pub struct Duplicate {
+    /// The package of the Cargo.toml next to the Cargo.lock.
+    pub package_name: String,
+    pub crate_name: String,
+    /// Every locked version of the crate, lowest first, followed by its source unless it
+    /// comes from crates.io.
+    pub versions: Vec<String>,
+    /// The Cargo.lock the duplicate was found in.
+    pub path: String,
+}
+
+/// Finds the crates locked at more than one version in every Cargo.lock under `args.path`,
+/// only looking at the crates matching `args.name` and `args.regex` when given.
+pub fn duplicates(args: &Args) -> Result<Vec<Duplicate>, MoleError> {
+    let matcher = args.matcher()?;
+    let parser = FileParser::new();
+    let mut found = Vec::new();
+
+    for (_, package) in file_explorer::explore(args)? {
+        let Some(ref lock) = package.clock else {
+            continue;
+        };
+        let lock_file = fs::read_to_string(lock)?;
+        let parsed: CLockFile = match toml::from_str(&lock_file) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                eprintln!("Unparseable file: {:?} {e}", lock);
+                continue;
+            }
+        };
+        let package_name = match package.ctoml {
+            Some(ref toml) => parser.parse_name(&fs::read_to_string(toml)?),
+            None => DEFAULT_PACKAGE_NAME.to_string(),
+        };
+
+        let mut versions: HashMap<String, Vec<(String, Source)>> = HashMap::new();
+        for locked in parsed.into_packages() {
+            if matcher.matches(&locked.name) {
+                let source = Source::from_lock(locked.source.as_deref());
+                versions
+                    .entry(locked.name)
+                    .or_default()
+                    .push((locked.version, source));
+            }
+        }
+
+        for (crate_name, mut versions) in versions {
+            // The same version can be locked from several sources, e.g. crates.io and a fork.
+            versions.sort_by(|(a, a_source), (b, b_source)| {
+                compare_versions(a, b).then(a_source.to_string().cmp(&b_source.to_string()))
+            });
+            versions.dedup();
+            if versions.len() > 1 {
+                found.push(Duplicate {
+                    package_name: package_name.clone(),
+                    crate_name,
+                    versions: versions.into_iter().map(describe_version).collect(),
+                    path: lock.to_string_lossy().to_string(),
+                });
+            }
+        }
+    }
+    found.sort_by(|a, b| a.path.cmp(&b.path).then(a.crate_name.cmp(&b.crate_name)));
+    Ok(found)
+}
+
+fn describe_version((version, source): (String, Source)) -> String {
+    match source {
+        Source::CratesIo => version,
+        source => format!("{version} ({source})"),
+    }
+}
+
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    match (Version::parse(a), Version::parse(b)) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::duplicates;
+    use crate::{cli::Args, fixture};
+    use clap::Parser;
+
+    const LOCK: &str = r#"
+    [[package]]
+    name = "app"
+    version = "0.1.0"
+
+    [[package]]
+    name = "log"
+    version = "0.4.22"
+    source = "registry+https://github.com/rust-lang/crates.io-index"
+
+    [[package]]
+    name = "serde"
+    version = "1.0.210"
+    source = "registry+https://github.com/rust-lang/crates.io-index"
+
+    [[package]]
+    name = "serde"
+    version = "1.0.210"
+    source = "git+https://github.com/me/serde?branch=fix#abc123"
+
+    [[package]]
+    name = "syn"
+    version = "1.0.109"
+    source = "registry+https://github.com/rust-lang/crates.io-index"
+
+    [[package]]
+    name = "syn"
+    version = "2.0.79"
+    source = "registry+https://github.com/rust-lang/crates.io-index"
+    "#;
+
+    #[test]
+    fn group_versions_by_crate_and_source() {
+        let dir = fixture::dir(&[
+            ("app/Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("app/Cargo.lock", LOCK),
+            ("lib/Cargo.lock", LOCK),
+        ]);
+        let args = Args::parse_from([
+            "mole",
+            "-p",
+            dir.path().to_str().unwrap(),
+            "-m",
+            "duplicates",
+        ]);
+
+        let found: Vec<_> = duplicates(&args)
+            .unwrap()
+            .into_iter()
+            .map(|duplicate| {
+                let versions = duplicate.versions.join(", ");
+                format!(
+                    "{} {} {versions}",
+                    duplicate.package_name, duplicate.crate_name
+                )
+            })
+            .collect();
+
+        let serde = "serde 1.0.210, 1.0.210 (git https://github.com/me/serde?branch=fix)";
+        assert_eq!(
+            found,
+            [
+                format!("app {serde}"),
+                "app syn 1.0.109, 2.0.79".to_string(),
+                format!("- {serde}"),
+                "- syn 1.0.109, 2.0.79".to_string(),
+            ]
+        );
+    }
+}
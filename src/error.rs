@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use crate::file_explorer::CargoFiles;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -7,11 +7,23 @@ pub enum MoleError {
     IoError(#[from] std::io::Error),
 
     #[error("SendError: {0}")]
-    SendError(#[from] std::sync::mpsc::SendError<(std::string::String, PathBuf)>),
+    SendError(#[from] std::sync::mpsc::SendError<(std::string::String, CargoFiles)>),
 
     #[error("ThreadPoolBuildError: {0}")]
     ThreadPoolBuildError(#[from] rayon::ThreadPoolBuildError),
 
     #[error("SemverError: {0}")]
     SemverError(#[from] semver::Error),
+
+    #[error("A dependency name or pattern is required in this mode")]
+    MissingName,
+
+    #[error("PatternError: {0}")]
+    PatternError(#[from] globset::Error),
+
+    #[error("RegexError: {0}")]
+    RegexError(#[from] regex::Error),
+
+    #[error("GlobError: {0}")]
+    GlobError(#[from] ignore::Error),
 }
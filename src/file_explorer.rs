@@ -1,12 +1,20 @@
 mod explorer;
 mod parallel_explorer;
+mod walker;
 
-use crate::{cli::Args, error};
+use crate::{cli::Args, error, parser::workspace::WorkspaceRoot};
 use hashbrown::HashMap;
 use std::{
     fs::{self, DirEntry},
     path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
 };
+pub(crate) use walker::absolute;
+use walker::{Ignores, Walker};
 
 pub const CTOML: &str = "Cargo.toml";
 pub const ETOML: &str = "toml";
@@ -14,36 +22,103 @@ pub const ETOML: &str = "toml";
 pub const CLOCK: &str = "Cargo.lock";
 pub const ELOCK: &str = "lock";
 
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct CargoFiles {
     pub ctoml: Option<PathBuf>,
     pub clock: Option<PathBuf>,
+    /// The workspace the project is a member of, if any.
+    pub workspace: Option<Arc<WorkspaceRoot>>,
 }
 
+/// Collects the cargo files of every project under `args.path`, keyed by directory.
 pub fn explore(args: &Args) -> Result<HashMap<String, CargoFiles>, error::MoleError> {
-    let mut files;
+    let walker = Walker::new(args)?;
+    let ignores = Ignores::for_root(&args.path, &walker);
+
+    let (sender, receiver) = mpsc::channel();
+    walk(args, sender, &walker, &ignores)?;
+
+    Ok(receiver.into_iter().collect())
+}
+
+/// Explores `args.path` on a background thread, sending the cargo files of each project
+/// through the returned receiver as soon as its directory has been read.
+pub fn stream(args: &Args) -> Result<Receiver<(String, CargoFiles)>, error::MoleError> {
+    let walker = Walker::new(args)?;
+    let ignores = Ignores::for_root(&args.path, &walker);
+    let args = args.clone();
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        match walk(&args, sender, &walker, &ignores) {
+            // The receiver stops listening once the output has been closed.
+            Ok(()) | Err(error::MoleError::SendError(_)) => {}
+            Err(e) => eprintln!("Error exploring: {} | {e}", args.path.display()),
+        }
+    });
+
+    Ok(receiver)
+}
+
+fn walk(
+    args: &Args,
+    sender: Sender<(String, CargoFiles)>,
+    walker: &Walker,
+    ignores: &Ignores,
+) -> Result<(), error::MoleError> {
     if args.threaded {
-        files = parallel_explorer::collect_files(&args.path, args.deep, args.symlinks)?;
+        parallel_explorer::collect_files(&args.path, sender, walker, ignores, args.threads)
     } else {
-        files = hashbrown::HashMap::new();
-        explorer::collect_files(&args.path, &mut files, args.deep, args.symlinks)?;
+        explorer::collect_files(&args.path, &sender, walker, ignores, 0)
     }
+}
+
+/// Splits the entries of a directory into the cargo files it holds and its subdirectories.
+fn split_entries(
+    entries: impl Iterator<Item = DirEntry>,
+    walker: &Walker,
+) -> (CargoFiles, Vec<PathBuf>) {
+    let mut files = CargoFiles::default();
+    let mut dirs = Vec::new();
 
-    Ok(files)
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            dirs.push(path);
+        } else if path.is_file() {
+            match path.file_name().and_then(|f| f.to_str()) {
+                Some(CTOML) => files.ctoml = Some(path),
+                Some(CLOCK) if walker.deep => files.clock = Some(path),
+                _ => {}
+            }
+        }
+    }
+    files.workspace = files
+        .ctoml
+        .as_ref()
+        .and_then(|ctoml| walker.workspace_of(ctoml));
+    (files, dirs)
 }
 
-fn filter_entries(
-    path: &PathBuf,
-    deep: bool,
-    symlinks: bool,
-) -> Result<impl Iterator<Item = DirEntry> + '_, error::MoleError> {
+/// Reads the entries of a directory, keeping only subdirectories and cargo files
+/// that are not pruned by ignore files or by the `--exclude`/`--include` globs.
+fn filter_entries<'a>(
+    path: &'a PathBuf,
+    walker: &'a Walker,
+    ignores: &'a Ignores,
+) -> Result<impl Iterator<Item = DirEntry> + 'a, error::MoleError> {
     let entries = fs::read_dir(path)?.filter_map(move |entry| match entry {
         Ok(entry) => {
-            if symlinks {
-                filter_entry_with_symlinks(entry, deep, symlinks)
+            let entry = if walker.symlinks {
+                filter_entry_with_symlinks(entry, walker.deep, walker.symlinks)
             } else {
-                filter_entry(entry, deep)
+                filter_entry(entry, walker.deep)
+            }?;
+            let entry_path = entry.path();
+            if walker.is_pruned(&entry_path, entry_path.is_dir(), ignores) {
+                return None;
             }
+            Some(entry)
         }
         Err(e) => {
             eprintln!("Error accessing entry: {} | {e}", path.display());
@@ -60,7 +135,7 @@ fn filter_entry_with_symlinks(entry: DirEntry, deep: bool, symlinks: bool) -> Op
         || path
             .extension()
             .and_then(|ext| ext.to_str())
-            .map_or(false, |ext| ext == ETOML || (deep && ext == ELOCK))
+            .is_some_and(|ext| ext == ETOML || (deep && ext == ELOCK))
     {
         return Some(entry);
     }
@@ -69,13 +144,191 @@ fn filter_entry_with_symlinks(entry: DirEntry, deep: bool, symlinks: bool) -> Op
 
 fn filter_entry(entry: DirEntry, deep: bool) -> Option<DirEntry> {
     let path = entry.path();
-    if path.is_dir()
+    if path.is_dir() && !path.is_symlink()
         || path
             .extension()
             .and_then(|ext| ext.to_str())
-            .map_or(false, |ext| ext == ETOML || (deep && ext == ELOCK))
+            .is_some_and(|ext| ext == ETOML || (deep && ext == ELOCK))
     {
         return Some(entry);
     }
     None
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixture;
+    use clap::Parser;
+    use tempfile::TempDir;
+
+    /// The cargo files found under `dir` by both explorers, relative to `dir`.
+    fn found(dir: &TempDir, flags: &[&str]) -> Vec<String> {
+        let path = dir.path().to_str().unwrap();
+        let search = |threaded: &[&str]| {
+            let args = Args::parse_from(["mole", "-p", path].iter().chain(flags).chain(threaded));
+            let mut files: Vec<String> = explore(&args)
+                .unwrap()
+                .into_values()
+                .flat_map(|files| files.ctoml.into_iter().chain(files.clock))
+                .map(|file| {
+                    let file = file.strip_prefix(dir.path()).unwrap();
+                    file.to_string_lossy().to_string()
+                })
+                .collect();
+            files.sort();
+            files
+        };
+
+        let files = search(&[]);
+        assert_eq!(files, search(&["--threaded"]));
+        files
+    }
+
+    #[test]
+    fn honour_nested_ignore_files() {
+        let dir = fixture::dir(&[
+            ("Cargo.toml", ""),
+            ("ignored/Cargo.toml", ""),
+            ("sub/.gitignore", "ignored/\n"),
+            ("sub/Cargo.toml", ""),
+            ("sub/ignored/Cargo.toml", ""),
+        ]);
+
+        assert_eq!(
+            found(&dir, &[]),
+            ["Cargo.toml", "ignored/Cargo.toml", "sub/Cargo.toml"]
+        );
+    }
+
+    #[test]
+    fn ignore_takes_precedence_over_gitignore() {
+        let dir = fixture::dir(&[
+            (".gitignore", "kept/\nskipped/\n"),
+            (".ignore", "!kept/\n"),
+            ("kept/Cargo.toml", ""),
+            ("skipped/Cargo.toml", ""),
+        ]);
+
+        assert_eq!(found(&dir, &[]), ["kept/Cargo.toml"]);
+    }
+
+    #[test]
+    fn no_ignore_reads_ignored_files() {
+        let dir = fixture::dir(&[
+            (".gitignore", "skipped/\n"),
+            (".git/Cargo.toml", ""),
+            ("skipped/Cargo.toml", ""),
+        ]);
+
+        assert_eq!(found(&dir, &[]), Vec::<String>::new());
+        assert_eq!(
+            found(&dir, &["--no-ignore"]),
+            [".git/Cargo.toml", "skipped/Cargo.toml"]
+        );
+    }
+
+    #[test]
+    fn match_globs_relative_to_the_search_directory() {
+        let dir = fixture::dir(&[
+            ("services/api/Cargo.toml", ""),
+            ("services/vendor/Cargo.toml", ""),
+            ("tools/Cargo.toml", ""),
+            ("vendor/serde/Cargo.toml", ""),
+        ]);
+
+        assert_eq!(
+            found(
+                &dir,
+                &["-i", "services/**", "-i", "vendor/**", "-e", "vendor/**"]
+            ),
+            ["services/api/Cargo.toml", "services/vendor/Cargo.toml"]
+        );
+    }
+
+    #[test]
+    fn read_ignored_locks_in_why_mode() {
+        let dir = fixture::dir(&[
+            (".gitignore", "Cargo.lock\n"),
+            ("Cargo.lock", ""),
+            ("Cargo.toml", ""),
+        ]);
+
+        assert_eq!(found(&dir, &["--deep"]), ["Cargo.toml"]);
+        assert_eq!(found(&dir, &["-m", "why"]), ["Cargo.lock", "Cargo.toml"]);
+    }
+
+    #[test]
+    fn stop_at_max_depth() {
+        let dir = fixture::dir(&[
+            ("Cargo.toml", ""),
+            ("a/Cargo.toml", ""),
+            ("a/b/Cargo.toml", ""),
+        ]);
+
+        assert_eq!(found(&dir, &["--max-depth", "0"]), Vec::<String>::new());
+        assert_eq!(found(&dir, &["--max-depth", "1"]), ["Cargo.toml"]);
+        assert_eq!(
+            found(&dir, &["--max-depth", "2"]),
+            ["Cargo.toml", "a/Cargo.toml"]
+        );
+    }
+
+    #[test]
+    fn skip_default_directories_unless_overridden() {
+        let dir = fixture::dir(&[
+            (".cargo/registry/Cargo.toml", ""),
+            ("Cargo.toml", ""),
+            ("target/package/Cargo.toml", ""),
+        ]);
+
+        assert_eq!(found(&dir, &[]), ["Cargo.toml"]);
+        assert_eq!(
+            found(&dir, &["--skip-dirs", "target"]),
+            [".cargo/registry/Cargo.toml", "Cargo.toml"]
+        );
+        assert_eq!(
+            found(&dir, &["--skip-dirs", ""]),
+            [
+                ".cargo/registry/Cargo.toml",
+                "Cargo.toml",
+                "target/package/Cargo.toml"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stop_at_symlink_cycles() {
+        let dir = fixture::dir(&[("Cargo.toml", ""), ("a/Cargo.toml", "")]);
+        std::os::unix::fs::symlink("..", dir.path().join("a/up")).unwrap();
+
+        assert_eq!(found(&dir, &[]), ["Cargo.toml", "a/Cargo.toml"]);
+        assert_eq!(found(&dir, &["--symlinks"]), ["Cargo.toml", "a/Cargo.toml"]);
+    }
+
+    #[test]
+    fn stream_the_projects_explore_finds() {
+        let dir = fixture::dir(&[
+            ("Cargo.toml", ""),
+            ("a/Cargo.toml", ""),
+            ("a/b/Cargo.toml", ""),
+        ]);
+        let path = dir.path().to_str().unwrap();
+
+        for threaded in [false, true] {
+            let mut args = Args::parse_from(["mole", "-p", path]);
+            args.threaded = threaded;
+            let mut explored: Vec<_> = explore(&args).unwrap().into_keys().collect();
+            let mut streamed: Vec<_> = stream(&args)
+                .unwrap()
+                .into_iter()
+                .map(|(dir, _)| dir)
+                .collect();
+            explored.sort();
+            streamed.sort();
+            assert_eq!(streamed.len(), 3);
+            assert_eq!(streamed, explored);
+        }
+    }
+}
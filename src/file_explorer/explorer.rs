@@ -1,52 +1,44 @@
 use crate::error::MoleError;
-use hashbrown::HashMap;
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::mpsc::Sender};
 
-use super::{filter_entries, CargoFiles, CLOCK, CTOML};
+use super::{filter_entries, split_entries, CargoFiles, Ignores, Walker};
 
 /// Collects all cargo files in a given directory and its subdirectories.
+/// The files found in each directory are sent through the given sender
+/// before its subdirectories are explored.
 ///
 /// # Arguments
 ///
 /// * `path` - The path to the directory to search in.
-/// * `target` - The hashmap to store the found files in.
-/// * `deep` - A flag to indicate whether to include Cargo.lock as well.
+/// * `sender` - The sender channel to send found files.
+/// * `walker` - The settings deciding which entries are explored.
+/// * `ignores` - The ignore rules inherited from the parent directories.
+/// * `depth` - The depth of `path` below the search root.
 pub fn collect_files(
     path: &PathBuf,
-    target: &mut HashMap<String, CargoFiles>,
-    deep: bool,
-    symlinks: bool,
+    sender: &Sender<(String, CargoFiles)>,
+    walker: &Walker,
+    ignores: &Ignores,
+    depth: usize,
 ) -> Result<(), MoleError> {
-    let entries = filter_entries(path, deep, symlinks);
+    if walker.is_too_deep(depth) || !walker.visit(path) {
+        return Ok(());
+    }
+    let ignores = ignores.enter(path, walker);
+    let entries = filter_entries(path, walker, &ignores);
 
     match entries {
         Err(e) => {
             eprintln!("Error accessing entry: {} | {}", path.display(), e);
         }
         Ok(entries) => {
-            for entry in entries {
-                let path = entry.path();
+            let (files, dirs) = split_entries(entries, walker);
+            if files.ctoml.is_some() || files.clock.is_some() {
+                sender.send((path.to_string_lossy().to_string(), files))?;
+            }
 
-                if path.is_dir() {
-                    collect_files(&path, target, deep, symlinks)?;
-                } else if path.is_file() {
-                    if let (Some(parent), Some(file_name)) = (
-                        path.parent().map(|p| p.to_string_lossy()),
-                        path.file_name().and_then(|f| f.to_str()),
-                    ) {
-                        match file_name {
-                            CTOML => {
-                                target.entry(parent.to_string()).or_default().ctoml =
-                                    Some(path.clone());
-                            }
-                            CLOCK if deep => {
-                                target.entry(parent.to_string()).or_default().clock =
-                                    Some(path.clone());
-                            }
-                            _ => {}
-                        }
-                    }
-                }
+            for dir in dirs {
+                collect_files(&dir, sender, walker, &ignores, depth + 1)?;
             }
         }
     }
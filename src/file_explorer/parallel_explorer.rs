@@ -1,39 +1,22 @@
-use super::filter_entries;
-use crate::{
-    error::MoleError,
-    file_explorer::{CargoFiles, CLOCK, CTOML},
-};
-use hashbrown::HashMap;
-use rayon::{prelude::*, ThreadPoolBuilder};
-use std::{path::PathBuf, sync::mpsc::Sender};
+use super::{filter_entries, split_entries, Ignores, Walker};
+use crate::{error::MoleError, file_explorer::CargoFiles, thread_pool};
+use rayon::prelude::*;
+use std::{num::NonZeroUsize, path::PathBuf, sync::mpsc::Sender};
 
+/// Explores the given directory on the shared thread pool, sending the cargo files
+/// found in each directory through the given sender.
 pub fn collect_files(
     path: &PathBuf,
-    deep: bool,
-    symlinks: bool,
-) -> Result<HashMap<String, CargoFiles>, MoleError> {
-    let mut files: HashMap<String, CargoFiles> = hashbrown::HashMap::new();
-
-    let (sender, receiver) = std::sync::mpsc::channel::<(String, PathBuf)>();
-
-    let pool = ThreadPoolBuilder::new().num_threads(4).build()?;
+    sender: Sender<(String, CargoFiles)>,
+    walker: &Walker,
+    ignores: &Ignores,
+    threads: Option<NonZeroUsize>,
+) -> Result<(), MoleError> {
+    let pool = thread_pool::get(threads)?;
     pool.install(|| -> Result<(), MoleError> {
-        explore(path, deep, sender, symlinks)?;
+        explore(path, walker, ignores, sender, 0)?;
         Ok(())
-    })?;
-
-    for (key, value) in receiver {
-        match value.file_name().and_then(|f| f.to_str()) {
-            Some(CTOML) => {
-                files.entry(key).or_default().ctoml = Some(value.clone());
-            }
-            Some(CLOCK) => {
-                files.entry(key).or_default().clock = Some(value.clone());
-            }
-            _ => {}
-        }
-    }
-    Ok(files)
+    })
 }
 
 /// Recursively searches for Cargo.toml and Cargo.lock files in a given directory.
@@ -42,52 +25,38 @@ pub fn collect_files(
 /// # Arguments
 ///
 /// * `path` - The path to the directory to search in.
-/// * `deep` - A flag to indicate whether to include Cargo.lock as well.
+/// * `walker` - The settings deciding which entries are explored.
+/// * `ignores` - The ignore rules inherited from the parent directories.
 /// * `sender` - The sender channel to send found files.
+/// * `depth` - The depth of `path` below the search root.
 pub fn explore(
     path: &PathBuf,
-    deep: bool,
-    sender: Sender<(String, PathBuf)>,
-    symlinks: bool,
+    walker: &Walker,
+    ignores: &Ignores,
+    sender: Sender<(String, CargoFiles)>,
+    depth: usize,
 ) -> Result<(), MoleError> {
-    let entries = filter_entries(path, deep, symlinks);
+    if walker.is_too_deep(depth) || !walker.visit(path) {
+        return Ok(());
+    }
+    let ignores = ignores.enter(path, walker);
+    let entries = filter_entries(path, walker, &ignores);
 
     match entries {
         Err(e) => {
             eprintln!("Error accessing entry: {} | {}", path.display(), e);
         }
         Ok(entries) => {
-            entries
-                .collect::<Vec<_>>()
-                .into_par_iter()
-                .with_max_len(1)
-                .for_each(|entry| {
-                    if let Err(e) = (|| -> Result<(), MoleError> {
-                        let path = entry.path();
+            let (files, dirs) = split_entries(entries, walker);
+            if files.ctoml.is_some() || files.clock.is_some() {
+                sender.send((path.to_string_lossy().to_string(), files))?;
+            }
 
-                        if path.is_dir() {
-                            explore(&path, deep, sender.clone(), symlinks)?;
-                        } else if path.is_file() {
-                            if let (Some(parent), Some(file_name)) = (
-                                path.parent().map(|p| p.to_string_lossy()),
-                                path.file_name().and_then(|f| f.to_str()),
-                            ) {
-                                match file_name {
-                                    CTOML => {
-                                        sender.send((parent.to_string(), path.clone()))?;
-                                    }
-                                    CLOCK if deep => {
-                                        sender.send((parent.to_string(), path.clone()))?;
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-                        Ok(())
-                    })() {
-                        eprintln!("Error processing entry: {} {}", path.display(), e);
-                    }
-                });
+            dirs.into_par_iter().with_max_len(1).for_each(|dir| {
+                if let Err(e) = explore(&dir, walker, &ignores, sender.clone(), depth + 1) {
+                    eprintln!("Error processing entry: {} {}", dir.display(), e);
+                }
+            });
         }
     }
 
@@ -0,0 +1,281 @@
+use super::{CLOCK, CTOML};
+use crate::{
+    cli::{Args, Mode},
+    error::MoleError,
+    parser::workspace::WorkspaceRoot,
+};
+use hashbrown::{HashMap, HashSet};
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    overrides::{Override, OverrideBuilder},
+};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+const GIT_DIR: &str = ".git";
+
+/// Settings shared by both explorers while walking the directory tree.
+pub struct Walker {
+    pub deep: bool,
+    pub symlinks: bool,
+    pub ignore_files: bool,
+    pub max_depth: Option<usize>,
+    /// Whether Cargo.lock files are read even when an ignore file excludes them.
+    read_ignored_locks: bool,
+    skip_dirs: Vec<String>,
+    overrides: Override,
+    /// Directories already explored, only tracked when following symlinks.
+    visited: Mutex<HashSet<DirId>>,
+    /// Directories already reported as skipped, so each one is warned about once.
+    warned: Mutex<HashSet<DirId>>,
+    /// The `[workspace]` of every manifest read while looking for workspace roots.
+    workspaces: Mutex<HashMap<PathBuf, Option<Arc<WorkspaceRoot>>>>,
+}
+
+impl Walker {
+    pub fn new(args: &Args) -> Result<Self, MoleError> {
+        let mut overrides = OverrideBuilder::new(&args.path);
+        for glob in &args.include {
+            overrides.add(glob)?;
+        }
+        for glob in &args.exclude {
+            overrides.add(&format!("!{glob}"))?;
+        }
+
+        Ok(Walker {
+            deep: args.deep || args.mode.needs_lock(),
+            symlinks: args.symlinks,
+            ignore_files: !args.no_ignore,
+            max_depth: args.max_depth,
+            // Libraries commonly ignore their Cargo.lock, which still pins the chains
+            // `why` reports.
+            read_ignored_locks: args.mode == Mode::Why,
+            skip_dirs: args
+                .skip_dirs
+                .iter()
+                .filter(|dir| !dir.is_empty())
+                .cloned()
+                .collect(),
+            overrides: overrides.build()?,
+            visited: Mutex::new(HashSet::new()),
+            warned: Mutex::new(HashSet::new()),
+            workspaces: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Whether the entries of a directory at the given depth are out of reach,
+    /// the search root being at depth 0.
+    pub fn is_too_deep(&self, depth: usize) -> bool {
+        self.max_depth.is_some_and(|max_depth| depth >= max_depth)
+    }
+
+    /// Marks a directory as explored, returning `false` if it was already reached through
+    /// another path, as happens when symlinks form a cycle or several symlinks lead to the
+    /// same directory. Which of those paths is explored first is not fixed with `--threaded`.
+    pub fn visit(&self, dir: &Path) -> bool {
+        if !self.symlinks {
+            return true;
+        }
+        let Some(id) = dir_id(dir) else {
+            return true;
+        };
+        // The sets are only ever inserted into, so they stay consistent after a panic.
+        let mut visited = self
+            .visited
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !visited.contains(&id) {
+            visited.insert(id);
+            return true;
+        }
+        drop(visited);
+        let first_warning = self
+            .warned
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(id);
+        if first_warning {
+            eprintln!("Skipping already visited directory: {}", dir.display());
+        }
+        false
+    }
+
+    /// The workspace a manifest belongs to. As with cargo, the root is the closest manifest
+    /// declaring a `[workspace]`, the manifest itself included, and has to list the
+    /// package as one of its members.
+    pub fn workspace_of(&self, manifest: &Path) -> Option<Arc<WorkspaceRoot>> {
+        let manifest = absolute(manifest);
+        let package_dir = manifest.parent()?;
+        let root = package_dir
+            .ancestors()
+            .map(|dir| dir.join(CTOML))
+            .filter(|candidate| candidate.is_file())
+            .find_map(|candidate| self.workspace_root(&candidate))?;
+        root.contains(package_dir).then_some(root)
+    }
+
+    fn workspace_root(&self, manifest: &Path) -> Option<Arc<WorkspaceRoot>> {
+        // The cache is only ever inserted into, so it stays consistent after a panic.
+        let workspaces = || {
+            self.workspaces
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+        };
+        if let Some(root) = workspaces().get(manifest) {
+            return root.clone();
+        }
+        let root = WorkspaceRoot::read(manifest, self.deep).map(Arc::new);
+        workspaces().insert(manifest.to_path_buf(), root.clone());
+        root
+    }
+
+    /// Whether the given path should be skipped before being read.
+    pub fn is_pruned(&self, path: &Path, is_dir: bool, ignores: &Ignores) -> bool {
+        if is_dir
+            && path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| self.skip_dirs.iter().any(|skip| skip == name))
+        {
+            return true;
+        }
+        if self.ignore_files
+            && ((is_dir && path.file_name().is_some_and(|name| name == GIT_DIR))
+                || (!(self.read_ignored_locks
+                    && path.file_name().is_some_and(|name| name == CLOCK))
+                    && ignores.is_ignored(path, is_dir)))
+        {
+            return true;
+        }
+        self.overrides.matched(path, is_dir).is_ignore()
+    }
+}
+
+/// The `.gitignore` and `.ignore` rules in effect for a directory, outermost first.
+#[derive(Clone, Default)]
+pub struct Ignores(Vec<Arc<Gitignore>>);
+
+impl Ignores {
+    /// Collects the ignore files of the ancestors of the search root, up to the root of
+    /// the git repository it lives in. The root's own ignore files are added by the
+    /// explorers when they enter it.
+    pub fn for_root(root: &Path, walker: &Walker) -> Self {
+        let mut ignores = Ignores::default();
+        if !walker.ignore_files {
+            return ignores;
+        }
+
+        let root = absolute(root);
+        let ancestors: Vec<&Path> = root.ancestors().skip(1).collect();
+        if let Some(repo_root) = ancestors.iter().position(|dir| dir.join(GIT_DIR).exists()) {
+            for dir in ancestors[..=repo_root].iter().rev() {
+                ignores = ignores.enter(dir, walker);
+            }
+        }
+        ignores
+    }
+
+    /// Returns the rules in effect inside `dir`, adding its own ignore files if any.
+    pub fn enter(&self, dir: &Path, walker: &Walker) -> Self {
+        if !walker.ignore_files {
+            return self.clone();
+        }
+
+        let dir = absolute(dir);
+        let mut builder = GitignoreBuilder::new(&dir);
+        let mut found = false;
+        for file in IGNORE_FILES.iter().map(|file| dir.join(file)) {
+            if file.is_file() {
+                found = true;
+                if let Some(e) = builder.add(&file) {
+                    eprintln!("Error reading ignore file: {} | {e}", file.display());
+                }
+            }
+        }
+        if !found {
+            return self.clone();
+        }
+
+        let mut ignores = self.clone();
+        match builder.build() {
+            Ok(gitignore) => ignores.0.push(Arc::new(gitignore)),
+            Err(e) => eprintln!("Error reading ignore files in: {} | {e}", dir.display()),
+        }
+        ignores
+    }
+
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = absolute(path);
+        self.0
+            .iter()
+            .rev()
+            .map(|gitignore| gitignore.matched(&path, is_dir))
+            .find(|matched| !matched.is_none())
+            .is_some_and(|matched| matched.is_ignore())
+    }
+}
+
+#[cfg(unix)]
+type DirId = (u64, u64);
+
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+/// Identifies a directory by the (device, inode) pair it resolves to.
+#[cfg(unix)]
+fn dir_id(dir: &Path) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::metadata(dir).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Identifies a directory by its canonical path where inodes are not available.
+#[cfg(not(unix))]
+fn dir_id(dir: &Path) -> Option<DirId> {
+    fs::canonicalize(dir).ok()
+}
+
+/// Ignore rules and workspace roots are looked up from absolute paths, so they match
+/// regardless of how the search root was spelled on the command line. As with cargo, `..`
+/// components are resolved lexically, without following symlinks.
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+    use crate::fixture;
+    use clap::Parser;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn warn_once_about_visited_directories() {
+        let dir = fixture::dir(&[("a/Cargo.toml", "")]);
+        let a = dir.path().join("a");
+        symlink("..", a.join("up")).unwrap();
+        let path = dir.path().to_str().unwrap();
+        let walker = Walker::new(&Args::parse_from(["mole", "-p", path, "--symlinks"])).unwrap();
+
+        assert!(walker.visit(dir.path()));
+        assert!(walker.visit(&a));
+        assert!(!walker.visit(&a.join("up")));
+        assert!(!walker.visit(&a.join("up/a/up")));
+        assert_eq!(walker.warned.lock().unwrap().len(), 1);
+    }
+}
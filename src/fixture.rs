@@ -0,0 +1,21 @@
+//! Directory trees written to temporary directories for tests.
+
+use std::{fs, path::Path};
+use tempfile::TempDir;
+
+/// Writes each `(path, contents)` pair, the path being relative to a new temporary
+/// directory which is removed when the returned handle is dropped.
+pub fn dir(files: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, contents) in files {
+        write(dir.path(), path, contents);
+    }
+    dir
+}
+
+/// Writes a file below `root`, creating its parent directories.
+pub fn write(root: &Path, path: &str, contents: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
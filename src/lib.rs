@@ -1,6 +1,44 @@
+pub mod cargo_config;
 pub mod cli;
+pub mod duplicates;
 pub mod error;
 pub mod file_explorer;
+#[cfg(test)]
+mod fixture;
+pub mod matcher;
 pub mod parser;
+pub mod patches;
 pub mod printer;
 pub mod semver_filter;
+pub mod stale;
+pub mod thread_pool;
+pub mod why;
+
+use cli::Args;
+use error::MoleError;
+use parser::{data::OutputRow, FileParser};
+use semver::VersionReq;
+
+/// Runs a full search: explores `args.path`, parses the cargo files found and applies
+/// the semver filter. Every dependency is reported when no name nor pattern is given.
+/// Parsing runs on the thread pool when `args.threaded` is set.
+pub fn search(args: &Args) -> Result<Vec<OutputRow>, MoleError> {
+    let matcher = args.matcher()?;
+    let filter = args.filter.as_deref().map(VersionReq::parse).transpose()?;
+    let files = file_explorer::explore(args)?;
+
+    let parser = FileParser::new();
+    let mut rows = if args.threaded {
+        parser.par_parse(files, &matcher, &*thread_pool::get(args.threads)?)?
+    } else {
+        parser.parse(files, &matcher)?
+    };
+
+    rows.retain(|row| args.keeps(row));
+    parser::cfg::annotate(&mut rows, &args.targets());
+    Ok(semver_filter::filter(
+        filter.as_ref(),
+        args.filter_mode,
+        rows,
+    ))
+}
@@ -1,34 +1,111 @@
 use clap::Parser;
 use mole::{
-    cli::Args,
-    error, file_explorer,
+    cli::{Args, Mode},
+    duplicates, error, file_explorer,
     parser::{self, data::OutputRow},
-    printer::print,
+    patches,
+    printer::{
+        ndjson::print_ndjson, print, print_chains, print_duplicates, print_patches, print_stale,
+    },
+    stale, why,
 };
 use semver::VersionReq;
 use std::process::ExitCode;
 
 fn main() -> ExitCode {
     let args = Args::parse();
-    let format = args.output;
 
-    match explore(args) {
+    match run(args) {
         Err(e) => {
             eprintln!("{}", e);
             ExitCode::FAILURE
         }
-        Ok(deps) => {
-            print(deps, &format);
-            ExitCode::SUCCESS
+        Ok(code) => code,
+    }
+}
+
+fn run(args: Args) -> Result<ExitCode, error::MoleError> {
+    let format = args.output;
+
+    match args.mode {
+        Mode::Search if format.is_streamed() => stream(args)?,
+        Mode::Search => print(explore(args)?, &format),
+        Mode::Why => print_chains(why::why(&args)?, &format),
+        Mode::Duplicates => {
+            let found = duplicates::duplicates(&args)?;
+            let failed = args.deny_duplicates && !found.is_empty();
+            print_duplicates(found, &format, args.group_by);
+            if failed {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Mode::Patches => print_patches(patches::patches(&args)?, &format),
+        Mode::Stale => {
+            let found = stale::stale_locks(&args)?;
+            let failed = args.deny_stale && !found.is_empty();
+            print_stale(found, &format);
+            if failed {
+                return Ok(ExitCode::FAILURE);
+            }
         }
     }
+    Ok(ExitCode::SUCCESS)
 }
 
 fn explore(args: Args) -> Result<Vec<OutputRow>, error::MoleError> {
+    mole::search(&args)
+}
+
+/// Parses and prints each project as soon as the explorer finds it, until stdout is closed.
+fn stream(args: Args) -> Result<(), error::MoleError> {
+    let matcher = args.matcher()?;
     let filter = args.filter.as_deref().map(VersionReq::parse).transpose()?;
+    let parser = parser::FileParser::new();
+    let targets = args.targets();
 
-    Ok(mole::semver_filter::filter(
-        filter,
-        parser::FileParser::new().parse(file_explorer::explore(&args)?, &args.name)?,
-    ))
+    for (_, files) in file_explorer::stream(&args)? {
+        let mut rows = parser.parse_project(files, &matcher)?;
+        rows.retain(|row| args.keeps(row));
+        parser::cfg::annotate(&mut rows, &targets);
+        let rows = mole::semver_filter::filter(filter.as_ref(), args.filter_mode, rows);
+        if !print_ndjson(rows) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::run;
+    use clap::Parser;
+    use mole::cli::Args;
+    use std::{fs, process::ExitCode};
+
+    #[test]
+    fn deny_duplicates_fails_when_duplicates_are_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = r#"
+        [[package]]
+        name = "syn"
+        version = "1.0.109"
+
+        [[package]]
+        name = "syn"
+        version = "2.0.79"
+        "#;
+        fs::write(dir.path().join("Cargo.lock"), lock).unwrap();
+        let path = dir.path().to_str().unwrap();
+        let code = |flags: &[&str]| {
+            let args = ["mole", "-p", path, "-m", "duplicates", "-o", "json"];
+            run(Args::parse_from(args.iter().chain(flags))).unwrap()
+        };
+
+        assert_eq!(code(&[]), ExitCode::SUCCESS);
+        assert_eq!(code(&["--deny-duplicates"]), ExitCode::FAILURE);
+        assert_eq!(
+            code(&["--deny-duplicates", "-n", "serde"]),
+            ExitCode::SUCCESS
+        );
+    }
 }
@@ -0,0 +1,76 @@
+use crate::error::MoleError;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::RegexSet;
+
+/// Characters turning a dependency name into a glob pattern.
+const GLOB_CHARS: [char; 3] = ['*', '?', '['];
+
+/// Decides which crates a search is about: exact names, glob patterns such as
+/// `tokio-*` and regular expressions, any of them matching being enough.
+#[derive(Debug, Clone)]
+pub struct DependencyMatcher {
+    names: Vec<String>,
+    globs: GlobSet,
+    regexes: RegexSet,
+}
+
+impl DependencyMatcher {
+    /// Builds a matcher from names, which are treated as globs when they contain glob
+    /// characters, and from regular expressions that must match the whole crate name.
+    pub fn new(names: &[String], regexes: &[String]) -> Result<Self, MoleError> {
+        let mut exact = Vec::new();
+        let mut globs = GlobSetBuilder::new();
+        for name in names {
+            if name.contains(GLOB_CHARS) {
+                globs.add(Glob::new(name)?);
+            } else {
+                exact.push(name.clone());
+            }
+        }
+
+        Ok(DependencyMatcher {
+            names: exact,
+            globs: globs.build()?,
+            regexes: RegexSet::new(regexes.iter().map(|regex| format!("^(?:{regex})$")))?,
+        })
+    }
+
+    /// Whether no name nor pattern was given.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.globs.is_empty() && self.regexes.is_empty()
+    }
+
+    /// Whether the crate is part of the search. A matcher without names nor patterns
+    /// matches every crate.
+    pub fn matches(&self, crate_name: &str) -> bool {
+        self.is_empty()
+            || self.names.iter().any(|name| name == crate_name)
+            || self.globs.is_match(crate_name)
+            || self.regexes.is_match(crate_name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DependencyMatcher;
+
+    #[test]
+    fn match_names_globs_and_regexes() {
+        let matcher = DependencyMatcher::new(
+            &["tokio".to_string(), "tokio-*".to_string()],
+            &["aws-sdk-.*".to_string()],
+        )
+        .unwrap();
+
+        assert!(matcher.matches("tokio"));
+        assert!(matcher.matches("tokio-util"));
+        assert!(matcher.matches("aws-sdk-s3"));
+        assert!(!matcher.matches("tokio_stream"));
+        assert!(!matcher.matches("my-aws-sdk-s3"));
+        assert!(!matcher.is_empty());
+
+        let inventory = DependencyMatcher::new(&[], &[]).unwrap();
+        assert!(inventory.is_empty());
+        assert!(inventory.matches("serde"));
+    }
+}
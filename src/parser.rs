@@ -1,151 +1,763 @@
+pub mod cfg;
 pub mod data;
+pub mod lock;
+pub mod overrides;
+pub mod source;
+pub mod workspace;
 
-use crate::{error::MoleError, file_explorer::CargoFiles};
-use data::{CLockFile, CTomlFile, Dependency, OutputRow};
+use crate::{
+    cargo_config::ConfigLoader,
+    error::MoleError,
+    file_explorer::{CargoFiles, CLOCK},
+    matcher::DependencyMatcher,
+};
+use data::{CLockFile, CTomlFile, Dependency, DependencyKind, LockPackage, Locked, OutputRow};
 use hashbrown::HashMap;
-use std::fs;
+use overrides::Override;
+use rayon::{prelude::*, ThreadPool};
+use semver::{Version, VersionReq};
+use source::Source;
+use std::{cmp::Ordering, fs};
 
-static DEFAULT_PACKAGE_NAME: &str = "-";
+pub(crate) static DEFAULT_PACKAGE_NAME: &str = "-";
 static DEFAULT_VERSION: &str = "-";
 
+/// The dependency table an entry was declared in, e.g.
+/// `[target.'cfg(unix)'.dev-dependencies]`.
+struct Section {
+    kind: DependencyKind,
+    cfg: Option<String>,
+}
+
 #[derive(Default)]
-pub struct FileParser;
+pub struct FileParser {
+    configs: ConfigLoader,
+}
 
 impl FileParser {
     pub fn new() -> Self {
-        FileParser
+        FileParser::default()
     }
 
     pub fn parse(
         &self,
         files: HashMap<String, CargoFiles>,
-        target_dep: &str,
+        matcher: &DependencyMatcher,
     ) -> Result<Vec<OutputRow>, MoleError> {
         let mut found = Vec::new();
 
         for (_, package) in files {
-            if let Some(ref toml) = package.ctoml {
-                // Parse .toml
-                let toml_file = fs::read_to_string(toml)?;
-                let parsed = self.parse_toml(&toml_file, target_dep, &toml.to_string_lossy());
-
-                let package_name;
-                if let Some(parsed) = &parsed
-                    .iter()
-                    .find(|p| p.package_name != DEFAULT_PACKAGE_NAME)
-                {
-                    package_name = parsed.package_name.clone();
-                } else {
-                    package_name = self.parse_name(&toml_file);
-                }
+            found.extend(self.parse_project(package, matcher)?);
+        }
+        found.sort_by(compare_rows);
+        Ok(found)
+    }
+
+    /// Parses every project on the given thread pool. Rows are returned in the same order
+    /// as [`FileParser::parse`].
+    pub fn par_parse(
+        &self,
+        files: HashMap<String, CargoFiles>,
+        matcher: &DependencyMatcher,
+        pool: &ThreadPool,
+    ) -> Result<Vec<OutputRow>, MoleError> {
+        let mut packages: Vec<_> = files.into_iter().collect();
+        packages.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let parsed = pool.install(|| {
+            packages
+                .into_par_iter()
+                .map(|(_, package)| self.parse_project(package, matcher))
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+
+        let mut found: Vec<OutputRow> = parsed.into_iter().flatten().collect();
+        found.sort_by(compare_rows);
+        Ok(found)
+    }
 
+    /// Parses the cargo files of a single project, for callers consuming the explorer's stream.
+    pub fn parse_project(
+        &self,
+        package: CargoFiles,
+        matcher: &DependencyMatcher,
+    ) -> Result<Vec<OutputRow>, MoleError> {
+        let mut found = Vec::new();
+
+        if let Some(ref toml) = package.ctoml {
+            // Parse .toml
+            let toml_file = fs::read_to_string(toml)?;
+            let path = toml.to_string_lossy();
+            let manifest = read_manifest(&toml_file, &path);
+            let package_name = manifest
+                .as_ref()
+                .and_then(|manifest| manifest.package.as_ref())
+                .map_or_else(
+                    || DEFAULT_PACKAGE_NAME.to_string(),
+                    |package| package.name.clone(),
+                );
+            let own_overrides = match (&package.workspace, &manifest) {
+                (None, Some(manifest)) => overrides::overrides(manifest, &path),
+                _ => Vec::new(),
+            };
+            // Only the overrides of the workspace root apply to its members.
+            let overrides = package
+                .workspace
+                .as_ref()
+                .map_or(&own_overrides, |root| &root.overrides);
+
+            let workspace_deps = package.workspace.as_ref().map(|root| &root.dependencies);
+            let mut parsed = manifest
+                .map(|manifest| self.manifest_rows(manifest, matcher, &path, workspace_deps))
+                .unwrap_or_default();
+            overrides::apply(&mut parsed, overrides);
+
+            // parse .lock, members of a workspace sharing the lockfile of its root
+            let shared_lock = package.workspace.as_ref().and_then(|root| {
+                let packages = root.locked_packages()?;
+                Some((root.dir_from(toml).join(CLOCK), packages))
+            });
+            if let Some(lock) = &package.clock {
+                let lock_file = fs::read_to_string(lock)?;
+                let locked =
+                    self.parse_lock(&lock_file, matcher, &lock.to_string_lossy(), package_name);
+                found.extend(attach_locked(parsed, locked, true));
+            } else if let Some((lock, packages)) = shared_lock {
+                let locked = self.lock_rows(
+                    packages.iter().cloned(),
+                    matcher,
+                    &lock.to_string_lossy(),
+                    package_name,
+                );
+                // The transitive dependencies of a shared lockfile are reported by the root.
+                found.extend(attach_locked(parsed, locked, false));
+            } else {
                 found.extend(parsed);
+            }
 
-                // parse .lock
-                if let Some(ref lock) = package.clock {
-                    let lock_file = fs::read_to_string(lock)?;
-                    let parsed = self.parse_lock(
-                        &lock_file,
-                        target_dep,
-                        &lock.to_string_lossy(),
-                        package_name,
-                    );
-                    found.extend(parsed);
-                }
+            if let Some(dir) = toml.parent() {
+                self.configs.load(dir).label(&mut found);
+            }
+
+            if let Some(root) = &package.workspace {
+                let workspace = root.dir_from(toml).to_string_lossy().to_string();
+                found
+                    .iter_mut()
+                    .for_each(|row| row.workspace = Some(workspace.clone()));
             }
         }
-        found.sort_by(|a, b| a.path.cmp(&b.path));
+        found.sort_by(compare_rows);
         Ok(found)
     }
 
-    fn parse_toml(&self, contents: &str, target_dep: &str, path: &str) -> Vec<OutputRow> {
+    /// Parses the dependencies of the manifest held in `contents`.
+    #[cfg(test)]
+    fn parse_toml(
+        &self,
+        contents: &str,
+        matcher: &DependencyMatcher,
+        path: &str,
+        workspace_deps: Option<&HashMap<String, Dependency>>,
+    ) -> Vec<OutputRow> {
+        read_manifest(contents, path)
+            .map(|toml| self.manifest_rows(toml, matcher, path, workspace_deps))
+            .unwrap_or_default()
+    }
+
+    /// Lists the dependencies of a manifest, resolving the inherited ones against the
+    /// `[workspace.dependencies]` of the workspace it belongs to.
+    fn manifest_rows(
+        &self,
+        toml: CTomlFile,
+        matcher: &DependencyMatcher,
+        path: &str,
+        workspace_deps: Option<&HashMap<String, Dependency>>,
+    ) -> Vec<OutputRow> {
         let mut res = Vec::new();
-        let parsed: Result<CTomlFile, _> = toml::from_str(contents);
-        match parsed {
-            Ok(toml) => {
-                let package_name = toml
-                    .package
-                    .map(|package| package.name)
-                    .unwrap_or_else(|| DEFAULT_PACKAGE_NAME.to_string());
-
-                let mut all_deps = vec![toml.dependencies, toml.dev_dependencies];
-
-                if let Some(targets) = toml.target.and_then(|target| target.targets) {
-                    targets.into_iter().for_each(|(_, target)| {
-                        all_deps.push(target.dependencies);
-                        all_deps.push(target.dev_dependencies);
-                    });
-                }
-
-                all_deps.into_iter().flatten().for_each(|dep| {
-                    if let Some(found) =
-                        self.parse_dependencies(dep, target_dep, path, &package_name)
-                    {
-                        res.push(found)
-                    }
-                });
+        let package_name = toml
+            .package
+            .map(|package| package.name)
+            .unwrap_or_else(|| DEFAULT_PACKAGE_NAME.to_string());
 
-                return res;
-            }
-            Err(e) => {
-                eprintln!("Unparseable file: {:?} {e}", path);
-            }
+        let mut all_deps = vec![
+            (DependencyKind::Normal, None, toml.dependencies),
+            (DependencyKind::Dev, None, toml.dev_dependencies),
+            (DependencyKind::Build, None, toml.build_dependencies),
+        ];
+
+        if let Some(targets) = toml.target.and_then(|target| target.targets) {
+            targets.into_iter().for_each(|(cfg, target)| {
+                all_deps.push((
+                    DependencyKind::Normal,
+                    Some(cfg.clone()),
+                    target.dependencies,
+                ));
+                all_deps.push((
+                    DependencyKind::Dev,
+                    Some(cfg.clone()),
+                    target.dev_dependencies,
+                ));
+                all_deps.push((DependencyKind::Build, Some(cfg), target.build_dependencies));
+            });
         }
+
+        all_deps
+            .into_iter()
+            .filter_map(|(kind, cfg, deps)| deps.map(|deps| (Section { kind, cfg }, deps)))
+            .for_each(|(section, dep)| {
+                res.extend(self.parse_dependencies(
+                    dep,
+                    &section,
+                    matcher,
+                    path,
+                    &package_name,
+                    workspace_deps,
+                ))
+            });
         res
     }
 
     fn parse_lock(
         &self,
         contents: &str,
-        dependency_name: &str,
+        matcher: &DependencyMatcher,
+        path: &str,
+        package_name: String,
+    ) -> Vec<OutputRow> {
+        toml::from_str::<CLockFile>(contents)
+            .map(|lock_file| self.lock_rows(lock_file.into_packages(), matcher, path, package_name))
+            .unwrap_or_default()
+    }
+
+    fn lock_rows(
+        &self,
+        packages: impl IntoIterator<Item = LockPackage>,
+        matcher: &DependencyMatcher,
         path: &str,
         package_name: String,
     ) -> Vec<OutputRow> {
         let mut res = Vec::new();
-        let parsed: Result<CLockFile, _> = toml::from_str(contents);
-        if let Ok(lock_file) = parsed {
-            for package in lock_file.package {
-                if package.name == dependency_name {
-                    res.push(OutputRow {
-                        package_name: package_name.clone(),
-                        dep_version: package.version,
-                        path: path.to_owned(),
-                    });
-                }
+        for package in packages {
+            if matcher.matches(&package.name) {
+                res.push(OutputRow {
+                    package_name: package_name.clone(),
+                    dep_name: package.name,
+                    dep_version: package.version,
+                    path: path.to_owned(),
+                    source: Some(Source::from_lock(package.source.as_deref())),
+                    checksum: package.checksum,
+                    ..Default::default()
+                });
             }
         }
         res
     }
 
-    fn parse_name(&self, contents: &str) -> String {
+    pub fn parse_name(&self, contents: &str) -> String {
         toml::from_str::<CTomlFile>(contents)
             .ok()
             .and_then(|toml| toml.package.map(|package| package.name))
             .unwrap_or_else(|| DEFAULT_PACKAGE_NAME.to_string())
     }
 
+    /// The `[patch]` and `[replace]` entries of a manifest.
+    pub fn parse_overrides(&self, contents: &str, path: &str) -> Vec<Override> {
+        toml::from_str::<CTomlFile>(contents)
+            .map(|toml| overrides::overrides(&toml, path))
+            .unwrap_or_default()
+    }
+
     fn parse_dependencies(
         &self,
         dependencies: HashMap<String, Dependency>,
-        target_dep: &str,
+        section: &Section,
+        matcher: &DependencyMatcher,
         path: &str,
         package_name: &str,
-    ) -> Option<OutputRow> {
+        workspace_deps: Option<&HashMap<String, Dependency>>,
+    ) -> Vec<OutputRow> {
+        let mut res = Vec::new();
         for (dep_name, dep) in dependencies {
-            if dep_name == target_dep {
-                let version = match dep {
-                    data::Dependency::Simple(version) => version,
-                    data::Dependency::Detailed(dependency_details) => dependency_details
-                        .version
-                        .unwrap_or(DEFAULT_VERSION.to_string()),
+            let inherited = dep.is_inherited();
+            let from = workspace_deps
+                .and_then(|deps| deps.get(&dep_name))
+                .filter(|_| inherited);
+            // An inherited dependency is renamed by the workspace entry, and is only resolved
+            // once matched so that unmatched ones do not warn about their features.
+            let crate_name = from.unwrap_or(&dep).crate_name(&dep_name).to_string();
+            if matcher.matches(&crate_name) {
+                let dep = match from {
+                    Some(from) => dep.inherit(&dep_name, from),
+                    None => dep,
                 };
-                return Some(OutputRow {
+                let alias = (crate_name != dep_name).then_some(dep_name);
+                let source = Source::from_manifest(&dep);
+                let (version, features, default_features, optional) = match dep {
+                    data::Dependency::Simple(version) => (version, Vec::new(), true, false),
+                    data::Dependency::Detailed(dependency_details) => (
+                        dependency_details
+                            .version
+                            .unwrap_or(DEFAULT_VERSION.to_string()),
+                        dependency_details.features.unwrap_or_default(),
+                        dependency_details.default_features.unwrap_or(true),
+                        dependency_details.optional.unwrap_or(false),
+                    ),
+                };
+                res.push(OutputRow {
                     package_name: package_name.to_owned(),
+                    dep_name: crate_name,
+                    kind: Some(section.kind),
+                    target: section.cfg.clone(),
                     dep_version: version,
                     path: path.to_string(),
+                    inherited,
+                    alias,
+                    source,
+                    features,
+                    default_features: Some(default_features),
+                    optional,
+                    ..Default::default()
                 });
             }
         }
-        None
+        res
+    }
+}
+
+/// Pairs each manifest row with the package it was locked to. Lock rows paired with
+/// a manifest row are dropped, the remaining ones being transitive dependencies which
+/// are only kept when `transitive` is set.
+fn attach_locked(rows: Vec<OutputRow>, locked: Vec<OutputRow>, transitive: bool) -> Vec<OutputRow> {
+    let mut used = vec![false; locked.len()];
+    let mut rows: Vec<OutputRow> = rows
+        .into_iter()
+        .map(|mut row| {
+            if let Some(id) = resolve_locked(&row, &locked) {
+                used[id] = true;
+                let package = &locked[id];
+                row.locked = Some(Locked {
+                    version: package.dep_version.clone(),
+                    source: package.source.clone(),
+                    checksum: package.checksum.clone(),
+                    path: package.path.clone(),
+                });
+            }
+            row
+        })
+        .collect();
+
+    rows.extend(
+        locked
+            .into_iter()
+            .zip(used)
+            .filter(|(_, used)| transitive && !used)
+            .map(|(row, _)| row),
+    );
+    rows
+}
+
+/// The lock row a manifest row was resolved to: the highest locked version satisfying
+/// the requirement, or the only locked version of the crate when none does, which
+/// happens when the lockfile is stale.
+fn resolve_locked(row: &OutputRow, locked: &[OutputRow]) -> Option<usize> {
+    let requirement = VersionReq::parse(&row.dep_version).ok();
+    // A patched or replaced dependency is locked from the source overriding it.
+    let locked_source = match &row.overridden {
+        Some(overridden) => overridden.source.as_ref(),
+        None => row.source.as_ref(),
+    };
+    let candidates: Vec<(usize, Version)> = locked
+        .iter()
+        .enumerate()
+        .filter(|(_, package)| package.dep_name == row.dep_name)
+        .filter(|(_, package)| match (&locked_source, &package.source) {
+            (Some(source), Some(locked)) => source.may_lock(locked),
+            _ => true,
+        })
+        .filter_map(|(id, package)| Some((id, Version::parse(&package.dep_version).ok()?)))
+        .collect();
+
+    let satisfying = candidates
+        .iter()
+        .filter(|(_, version)| requirement.as_ref().is_none_or(|req| req.matches(version)))
+        .max_by(|(_, a), (_, b)| a.cmp(b));
+    match (satisfying, candidates.as_slice()) {
+        (Some((id, _)), _) => Some(*id),
+        (None, [(id, _)]) => Some(*id),
+        _ => None,
+    }
+}
+
+/// Parses a manifest, reporting it when it cannot be parsed.
+fn read_manifest(contents: &str, path: &str) -> Option<CTomlFile> {
+    toml::from_str(contents)
+        .map_err(|e| eprintln!("Unparseable file: {:?} {e}", path))
+        .ok()
+}
+
+fn compare_rows(a: &OutputRow, b: &OutputRow) -> Ordering {
+    a.workspace
+        .cmp(&b.workspace)
+        .then(a.path.cmp(&b.path))
+        .then(a.dep_name.cmp(&b.dep_name))
+        .then(a.kind.cmp(&b.kind))
+        .then(a.target.cmp(&b.target))
+        .then(a.alias.cmp(&b.alias))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{attach_locked, data::DependencyKind, overrides, read_manifest, FileParser};
+    use crate::{cli::Args, file_explorer, fixture, matcher::DependencyMatcher, thread_pool};
+    use clap::Parser;
+
+    #[test]
+    fn parse_toml_reports_every_section() {
+        let toml_content = r#"
+        [package]
+        name = "test"
+        version = "0.1.0"
+
+        [dependencies]
+        tokio = "1.0"
+        tokio1 = { package = "tokio", version = "1.38" }
+
+        [dev-dependencies]
+        tokio = { version = "1.40", features = ["full"] }
+
+        [target.'cfg(unix)'.dependencies]
+        tokio = "1.41"
+
+        [target.'cfg(windows)'.build-dependencies]
+        tokio = "1.42"
+        "#;
+
+        let matcher = DependencyMatcher::new(&["tokio".to_string()], &[]).unwrap();
+        let mut rows = FileParser::new().parse_toml(toml_content, &matcher, "Cargo.toml", None);
+        rows.sort_by(|a, b| a.dep_version.cmp(&b.dep_version));
+
+        let found: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.kind,
+                    row.target.as_deref(),
+                    row.alias.as_deref(),
+                    row.dep_version.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some(DependencyKind::Normal), None, None, "1.0"),
+                (Some(DependencyKind::Normal), None, Some("tokio1"), "1.38"),
+                (Some(DependencyKind::Dev), None, None, "1.40"),
+                (
+                    Some(DependencyKind::Normal),
+                    Some("cfg(unix)"),
+                    None,
+                    "1.41"
+                ),
+                (
+                    Some(DependencyKind::Build),
+                    Some("cfg(windows)"),
+                    None,
+                    "1.42"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn match_renamed_dependencies() {
+        let toml_content = r#"
+        [dependencies]
+        serde1 = { package = "serde", version = "1" }
+        "#;
+        let parse = |name: &str| {
+            let matcher = DependencyMatcher::new(&[name.to_string()], &[]).unwrap();
+            FileParser::new().parse_toml(toml_content, &matcher, "Cargo.toml", None)
+        };
+
+        let rows = parse("serde");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].dep_name, "serde");
+        assert_eq!(rows[0].alias.as_deref(), Some("serde1"));
+        assert!(parse("serde1").is_empty());
+    }
+
+    #[test]
+    fn match_inherited_dependencies_by_workspace_name() {
+        let workspace_deps = toml::from_str(
+            r#"
+            json = { package = "serde_json", version = "1" }
+            log = "0.4"
+            "#,
+        )
+        .unwrap();
+        let toml_content = r#"
+        [dependencies]
+        json = { workspace = true }
+        log = { workspace = true, default-features = false }
+        "#;
+        let parse = |name: &str| {
+            let matcher = DependencyMatcher::new(&[name.to_string()], &[]).unwrap();
+            FileParser::new().parse_toml(
+                toml_content,
+                &matcher,
+                "Cargo.toml",
+                Some(&workspace_deps),
+            )
+        };
+
+        let rows = parse("serde_json");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].dep_name, "serde_json");
+        assert_eq!(rows[0].alias.as_deref(), Some("json"));
+        assert_eq!(rows[0].dep_version, "1");
+        assert!(rows[0].inherited);
+        assert!(parse("json").is_empty());
+    }
+
+    #[test]
+    fn report_locked_sources_and_checksums() {
+        let lock_content = r#"
+        version = 4
+
+        [[package]]
+        name = "app"
+        version = "0.1.0"
+        dependencies = [
+         "serde",
+        ]
+
+        [[package]]
+        name = "serde"
+        version = "1.0.217"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+        checksum = "02fc4265df13d6fa1d00ecff087228cc0a2b5f3c0e87e258d8b94a156e984c70"
+
+        [[package]]
+        name = "serde_json"
+        version = "1.0.134"
+        source = "git+https://github.com/serde-rs/json?branch=master#a1b2c3"
+        "#;
+
+        let matcher = DependencyMatcher::new(&[], &[]).unwrap();
+        let mut rows =
+            FileParser::new().parse_lock(lock_content, &matcher, "Cargo.lock", "app".to_string());
+        rows.sort_by(|a, b| a.dep_name.cmp(&b.dep_name));
+
+        let found: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.dep_name.as_str(),
+                    row.source.as_ref().map(|source| source.to_string()),
+                    row.checksum.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("app", Some("path".to_string()), None),
+                (
+                    "serde",
+                    Some("crates.io".to_string()),
+                    Some("02fc4265df13d6fa1d00ecff087228cc0a2b5f3c0e87e258d8b94a156e984c70")
+                ),
+                (
+                    "serde_json",
+                    Some("git https://github.com/serde-rs/json?branch=master".to_string()),
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn attach_locked_versions() {
+        let toml_content = r#"
+        [dependencies]
+        serde = "1.0.200"
+        syn = "2"
+        "#;
+        let lock_content = r#"
+        version = 4
+
+        [[package]]
+        name = "serde"
+        version = "1.0.150"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+
+        [[package]]
+        name = "syn"
+        version = "1.0.109"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+
+        [[package]]
+        name = "syn"
+        version = "2.0.94"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+        "#;
+
+        let parser = FileParser::new();
+        let matcher = DependencyMatcher::new(&[], &[]).unwrap();
+        let rows = parser.parse_toml(toml_content, &matcher, "Cargo.toml", None);
+        let locked = parser.parse_lock(lock_content, &matcher, "Cargo.lock", "test".to_string());
+        let mut rows = attach_locked(rows, locked, true);
+        rows.sort_by(|a, b| a.path.cmp(&b.path).then(a.dep_name.cmp(&b.dep_name)));
+
+        let found: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.dep_name.as_str(),
+                    row.dep_version.as_str(),
+                    row.locked.as_ref().map(|locked| locked.version.as_str()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("syn", "1.0.109", None),
+                ("serde", "1.0.200", Some("1.0.150")),
+                ("syn", "2", Some("2.0.94")),
+            ]
+        );
+    }
+
+    #[test]
+    fn attach_patched_locked_versions() {
+        let toml_content = r#"
+        [dependencies]
+        log = "0.4"
+        serde = "1"
+
+        [patch.crates-io]
+        log = { path = "../log" }
+        serde = { git = "https://github.com/me/serde", branch = "fork" }
+        "#;
+        let lock_content = r#"
+        version = 4
+
+        [[package]]
+        name = "log"
+        version = "0.4.22"
+
+        [[package]]
+        name = "serde"
+        version = "1.0.210"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+
+        [[package]]
+        name = "serde"
+        version = "1.0.215"
+        source = "git+https://github.com/me/serde?branch=fork#a1b2c3"
+        "#;
+
+        let parser = FileParser::new();
+        let matcher = DependencyMatcher::new(&[], &[]).unwrap();
+        let mut rows = parser.parse_toml(toml_content, &matcher, "Cargo.toml", None);
+        let manifest = read_manifest(toml_content, "Cargo.toml").unwrap();
+        overrides::apply(&mut rows, &overrides::overrides(&manifest, "Cargo.toml"));
+        let locked = parser.parse_lock(lock_content, &matcher, "Cargo.lock", "test".to_string());
+        let mut rows = attach_locked(rows, locked, true);
+        rows.sort_by(|a, b| a.path.cmp(&b.path).then(a.dep_name.cmp(&b.dep_name)));
+
+        let found: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.dep_name.as_str(),
+                    row.dep_version.as_str(),
+                    row.locked.as_ref().map(|locked| locked.version.as_str()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                // Left unused by the patch, the crates.io version is reported on its own.
+                ("serde", "1.0.210", None),
+                ("log", "0.4", Some("0.4.22")),
+                ("serde", "1", Some("1.0.215")),
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_rows_by_source_kind() {
+        let toml_content = r#"
+        [dependencies]
+        serde = "1"
+        internal = { version = "0.3", registry = "internal" }
+        fork = { git = "https://github.com/me/fork", branch = "main" }
+        local = { path = "../local" }
+        inherited = { workspace = true }
+        "#;
+
+        let matcher = DependencyMatcher::new(&[], &[]).unwrap();
+        let mut rows = FileParser::new().parse_toml(toml_content, &matcher, "Cargo.toml", None);
+        rows.sort_by(|a, b| a.dep_name.cmp(&b.dep_name));
+
+        let kept = |flags: &[&str]| -> Vec<&str> {
+            let args = Args::parse_from([&["mole"], flags].concat());
+            rows.iter()
+                .filter(|row| args.keeps(row))
+                .map(|row| row.dep_name.as_str())
+                .collect()
+        };
+        assert_eq!(kept(&["--source", "crates-io"]), ["inherited", "serde"]);
+        assert_eq!(kept(&["--source", "registry"]), ["inherited", "internal"]);
+        assert_eq!(kept(&["--source", "git"]), ["fork", "inherited"]);
+        assert_eq!(kept(&["--source", "path"]), ["inherited", "local"]);
+        assert_eq!(
+            kept(&["--source", "git,path"]),
+            ["fork", "inherited", "local"]
+        );
+        assert_eq!(kept(&[]).len(), 5);
+    }
+
+    #[test]
+    fn par_parse_matches_parse() {
+        let manifest = |name: &str| {
+            format!(
+                "[package]\nname = \"{name}\"\n\n[dependencies]\nserde = \"1\"\nlog = \"0.4\"\n"
+            )
+        };
+        let lock = r#"
+        [[package]]
+        name = "log"
+        version = "0.4.22"
+
+        [[package]]
+        name = "serde"
+        version = "1.0.210"
+        "#;
+        let (a, b, c) = (manifest("a"), manifest("b"), manifest("c"));
+        let dir = fixture::dir(&[
+            ("a/Cargo.toml", &a),
+            ("a/Cargo.lock", lock),
+            ("b/Cargo.toml", &b),
+            ("b/c/Cargo.toml", &c),
+        ]);
+        let args = Args::parse_from(["mole", "-p", dir.path().to_str().unwrap(), "--deep"]);
+        let matcher = args.matcher().unwrap();
+        let parser = FileParser::new();
+        let pool = thread_pool::get(args.threads).unwrap();
+
+        let parsed = parser
+            .parse(file_explorer::explore(&args).unwrap(), &matcher)
+            .unwrap();
+        let par_parsed = parser
+            .par_parse(file_explorer::explore(&args).unwrap(), &matcher, &pool)
+            .unwrap();
+
+        assert_eq!(parsed.len(), 8);
+        assert_eq!(format!("{parsed:?}"), format!("{par_parsed:?}"));
     }
 }
@@ -0,0 +1,455 @@
+use super::data::OutputRow;
+use serde::Serialize;
+use std::{iter::Peekable, str::Chars};
+
+/// Whether a dependency applies to one of the target triples given with `--target`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TargetMatch {
+    pub triple: String,
+    pub applies: bool,
+}
+
+/// A `cfg(...)` predicate, as used in `[target.'cfg(...)'.dependencies]` tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cfg {
+    All(Vec<Cfg>),
+    Any(Vec<Cfg>),
+    Not(Box<Cfg>),
+    /// `key = "value"`, e.g. `target_os = "linux"`.
+    KeyValue(String, String),
+    /// A bare name, e.g. `unix`.
+    Name(String),
+}
+
+impl Cfg {
+    /// Parses a `cfg(...)` expression, the `cfg(` wrapper included.
+    pub fn parse(expression: &str) -> Result<Cfg, String> {
+        let mut tokens = Tokens::new(expression);
+        if tokens.ident()? != "cfg" {
+            return Err(format!("Expected cfg(...): {expression}"));
+        }
+        tokens.expect('(')?;
+        let cfg = tokens.predicate()?;
+        tokens.expect(')')?;
+        match tokens.next_char() {
+            None => Ok(cfg),
+            Some(c) => Err(format!("Unexpected '{c}' in: {expression}")),
+        }
+    }
+
+    pub fn eval(&self, target: &TargetInfo) -> bool {
+        match self {
+            Cfg::All(cfgs) => cfgs.iter().all(|cfg| cfg.eval(target)),
+            Cfg::Any(cfgs) => cfgs.iter().any(|cfg| cfg.eval(target)),
+            Cfg::Not(cfg) => !cfg.eval(target),
+            Cfg::KeyValue(key, value) => target.has(key, value),
+            Cfg::Name(name) => target.family.iter().any(|family| family == name),
+        }
+    }
+}
+
+struct Tokens<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(expression: &'a str) -> Self {
+        Tokens {
+            chars: expression.chars().peekable(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.next()
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.peek().copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next_char() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("Expected '{expected}', found '{c}'")),
+            None => Err(format!("Expected '{expected}', found the end")),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+        let mut ident = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+            ident.push(c);
+        }
+        if ident.is_empty() {
+            return Err("Expected an identifier".to_string());
+        }
+        Ok(ident)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        for c in self.chars.by_ref() {
+            if c == '"' {
+                return Ok(string);
+            }
+            string.push(c);
+        }
+        Err("Unterminated string".to_string())
+    }
+
+    fn predicate(&mut self) -> Result<Cfg, String> {
+        let ident = self.ident()?;
+        match (ident.as_str(), self.peek()) {
+            ("all" | "any" | "not", Some('(')) => {
+                self.expect('(')?;
+                let mut cfgs = Vec::new();
+                while self.peek() != Some(')') {
+                    cfgs.push(self.predicate()?);
+                    if self.peek() == Some(',') {
+                        self.expect(',')?;
+                    } else {
+                        break;
+                    }
+                }
+                self.expect(')')?;
+                match ident.as_str() {
+                    "all" => Ok(Cfg::All(cfgs)),
+                    "any" => Ok(Cfg::Any(cfgs)),
+                    _ if cfgs.len() == 1 => Ok(Cfg::Not(Box::new(cfgs.remove(0)))),
+                    _ => Err("not() takes exactly one predicate".to_string()),
+                }
+            }
+            (_, Some('=')) => {
+                self.expect('=')?;
+                Ok(Cfg::KeyValue(ident, self.string()?))
+            }
+            _ => Ok(Cfg::Name(ident)),
+        }
+    }
+}
+
+/// The `cfg` values of a target triple, derived from the triple itself since mole does
+/// not query rustc. Only the common architectures and operating systems are known.
+#[derive(Debug, Clone)]
+pub struct TargetInfo {
+    pub triple: String,
+    arch: String,
+    vendor: String,
+    os: String,
+    env: String,
+    abi: String,
+    family: Vec<String>,
+    pointer_width: &'static str,
+    endian: &'static str,
+}
+
+impl TargetInfo {
+    pub fn from_triple(triple: &str) -> Self {
+        let parts: Vec<&str> = triple.split('-').collect();
+        let raw_arch = parts.first().copied().unwrap_or_default();
+        let arch = match raw_arch {
+            "i386" | "i586" | "i686" => "x86",
+            "powerpc64le" => "powerpc64",
+            "mipsel" => "mips",
+            "mips64el" => "mips64",
+            arch if arch.starts_with("aarch64") => "aarch64",
+            arch if arch.starts_with("arm") || arch.starts_with("thumb") => "arm",
+            arch if arch.starts_with("riscv64") => "riscv64",
+            arch if arch.starts_with("riscv32") => "riscv32",
+            arch => arch,
+        };
+
+        let vendor = match parts.get(1) {
+            Some(vendor) if KNOWN_VENDORS.contains(vendor) && parts.len() > 2 => *vendor,
+            _ => "unknown",
+        };
+        // Android triples also name linux, e.g. aarch64-linux-android.
+        let os = match parts
+            .iter()
+            .skip(1)
+            .find(|part| part.starts_with("android"))
+        {
+            Some(_) => Some("android"),
+            None => parts
+                .iter()
+                .skip(1)
+                .find_map(|part| os_name(part))
+                .or((parts.get(2) == Some(&"unknown")).then_some("unknown")),
+        };
+        if !KNOWN_ARCHES.contains(&arch) {
+            eprintln!(
+                "Unknown architecture in target: {triple}, assuming target_arch = \"{arch}\""
+            );
+        }
+        let os = os.unwrap_or_else(|| {
+            eprintln!(
+                "Unknown operating system in target: {triple}, assuming target_os = \"none\""
+            );
+            "none"
+        });
+
+        let last = parts.last().copied().unwrap_or_default();
+        let env = ["gnu", "musl", "msvc", "sgx", "uclibc", "newlib", "ohos"]
+            .into_iter()
+            .find(|env| last.starts_with(env) && parts.len() > 2)
+            .unwrap_or("");
+        let abi = ["eabihf", "eabi", "llvm", "macabi", "sim"]
+            .into_iter()
+            .find(|abi| last.ends_with(abi) && parts.len() > 2)
+            .unwrap_or("");
+
+        let mut family = Vec::new();
+        if UNIX_OSES.contains(&os) {
+            family.push("unix".to_string());
+        }
+        if os == "windows" {
+            family.push("windows".to_string());
+        }
+        if arch.starts_with("wasm") {
+            family.push("wasm".to_string());
+        }
+
+        let pointer_width = match arch {
+            "x86_64" | "aarch64" | "riscv64" | "powerpc64" | "s390x" | "sparc64" | "mips64"
+            | "loongarch64" | "wasm64" => "64",
+            "msp430" | "avr" => "16",
+            _ => "32",
+        };
+        let endian = match raw_arch {
+            "powerpc" | "powerpc64" | "s390x" | "sparc" | "sparc64" | "mips" | "mips64" => "big",
+            arch if arch.starts_with("armeb") || arch.ends_with("_be") => "big",
+            _ => "little",
+        };
+
+        TargetInfo {
+            triple: triple.to_string(),
+            arch: arch.to_string(),
+            vendor: vendor.to_string(),
+            os: os.to_string(),
+            env: env.to_string(),
+            abi: abi.to_string(),
+            family,
+            pointer_width,
+            endian,
+        }
+    }
+
+    fn has(&self, key: &str, value: &str) -> bool {
+        match key {
+            "target_arch" => self.arch == value,
+            "target_vendor" => self.vendor == value,
+            "target_os" => self.os == value,
+            "target_env" => self.env == value,
+            "target_abi" => self.abi == value,
+            "target_family" => self.family.iter().any(|family| family == value),
+            "target_pointer_width" => self.pointer_width == value,
+            "target_endian" => self.endian == value,
+            _ => false,
+        }
+    }
+
+    /// Whether a `[target.<key>]` table applies to this target, its key being either a
+    /// `cfg(...)` expression or a target triple.
+    pub fn matches(&self, key: &str) -> Result<bool, String> {
+        if key.trim_start().starts_with("cfg") {
+            Ok(Cfg::parse(key)?.eval(self))
+        } else {
+            Ok(key == self.triple)
+        }
+    }
+}
+
+const KNOWN_ARCHES: [&str; 19] = [
+    "x86",
+    "x86_64",
+    "aarch64",
+    "arm",
+    "arm64ec",
+    "riscv32",
+    "riscv64",
+    "powerpc",
+    "powerpc64",
+    "s390x",
+    "sparc",
+    "sparc64",
+    "mips",
+    "mips64",
+    "loongarch64",
+    "wasm32",
+    "wasm64",
+    "msp430",
+    "avr",
+];
+const KNOWN_VENDORS: [&str; 8] = [
+    "pc", "apple", "unknown", "fortanix", "nvidia", "wrs", "uwp", "sun",
+];
+const UNIX_OSES: [&str; 16] = [
+    "linux",
+    "macos",
+    "ios",
+    "tvos",
+    "watchos",
+    "visionos",
+    "android",
+    "freebsd",
+    "netbsd",
+    "openbsd",
+    "dragonfly",
+    "solaris",
+    "illumos",
+    "emscripten",
+    "fuchsia",
+    "redox",
+];
+
+/// The `target_os` a triple component stands for, if any.
+fn os_name(part: &str) -> Option<&'static str> {
+    let os = match part {
+        "darwin" | "macos" => "macos",
+        "ios" => "ios",
+        "tvos" => "tvos",
+        "watchos" => "watchos",
+        "visionos" => "visionos",
+        "windows" => "windows",
+        "wasi" | "wasip1" | "wasip2" => "wasi",
+        "emscripten" => "emscripten",
+        "freebsd" => "freebsd",
+        "netbsd" => "netbsd",
+        "openbsd" => "openbsd",
+        "dragonfly" => "dragonfly",
+        "solaris" => "solaris",
+        "illumos" => "illumos",
+        "fuchsia" => "fuchsia",
+        "redox" => "redox",
+        "uefi" => "uefi",
+        "none" => "none",
+        part if part.starts_with("linux") => "linux",
+        _ => return None,
+    };
+    Some(os)
+}
+
+/// Records, for every dependency declared in a `[target.<key>]` table, whether it applies
+/// to each of the given targets. Unconditional dependencies apply to every target.
+pub fn annotate(rows: &mut [OutputRow], targets: &[TargetInfo]) {
+    if targets.is_empty() {
+        return;
+    }
+    for row in rows.iter_mut().filter(|row| row.kind.is_some()) {
+        row.applies = targets
+            .iter()
+            .map(|target| TargetMatch {
+                triple: target.triple.clone(),
+                applies: match &row.target {
+                    None => true,
+                    Some(key) => target.matches(key).unwrap_or_else(|e| {
+                        eprintln!("Unparseable target: {key} in {} | {e}", row.path);
+                        false
+                    }),
+                },
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{annotate, Cfg, TargetInfo};
+    use crate::{matcher::DependencyMatcher, parser::FileParser};
+
+    #[test]
+    fn evaluate_cfg_expressions() {
+        let musl = TargetInfo::from_triple("x86_64-unknown-linux-musl");
+        let windows = TargetInfo::from_triple("x86_64-pc-windows-msvc");
+        let wasm = TargetInfo::from_triple("wasm32-unknown-unknown");
+        let apple = TargetInfo::from_triple("aarch64-apple-darwin");
+
+        let cases = [
+            ("cfg(unix)", [true, false, false, true]),
+            ("cfg(windows)", [false, true, false, false]),
+            (
+                r#"cfg(all(target_os = "linux", target_env = "musl"))"#,
+                [true, false, false, false],
+            ),
+            (
+                r#"cfg(not(any(target_arch = "wasm32", target_os = "macos")))"#,
+                [true, true, false, false],
+            ),
+            (
+                r#"cfg(all(target_family = "wasm", target_pointer_width = "32"))"#,
+                [false, false, true, false],
+            ),
+            (
+                r#"cfg(all(target_vendor = "apple", target_endian = "little"))"#,
+                [false, false, false, true],
+            ),
+            ("x86_64-pc-windows-msvc", [false, true, false, false]),
+        ];
+        for (key, expected) in cases {
+            let found = [&musl, &windows, &wasm, &apple].map(|target| target.matches(key).unwrap());
+            assert_eq!(found, expected, "{key}");
+        }
+
+        assert_eq!(
+            Cfg::parse(r#"cfg( any( unix , feature = "x" ) )"#),
+            Ok(Cfg::Any(vec![
+                Cfg::Name("unix".to_string()),
+                Cfg::KeyValue("feature".to_string(), "x".to_string())
+            ]))
+        );
+        assert!(Cfg::parse("cfg(all(unix)").is_err());
+        assert!(Cfg::parse(r#"cfg(target_os = "linux)"#).is_err());
+    }
+
+    #[test]
+    fn annotate_dependency_rows() {
+        let toml_content = r#"
+        [dependencies]
+        serde = "1"
+
+        [target.'cfg(unix)'.dependencies]
+        libc = "0.2"
+
+        [target.'cfg(windows)'.dependencies]
+        windows-sys = "0.59"
+
+        [target.'cfg(all(unix, not(target_env = "musl")))'.dependencies]
+        openssl = "0.10"
+        "#;
+        let matcher = DependencyMatcher::new(&[], &[]).unwrap();
+        let mut rows = FileParser::new().parse_toml(toml_content, &matcher, "Cargo.toml", None);
+        rows.sort_by(|a, b| a.dep_name.cmp(&b.dep_name));
+
+        let targets = [
+            TargetInfo::from_triple("x86_64-unknown-linux-musl"),
+            TargetInfo::from_triple("x86_64-pc-windows-msvc"),
+        ];
+        annotate(&mut rows, &targets);
+
+        let applies: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                let applies: Vec<_> = row.applies.iter().map(|target| target.applies).collect();
+                (row.dep_name.as_str(), applies)
+            })
+            .collect();
+        assert_eq!(
+            applies,
+            vec![
+                ("libc", vec![true, false]),
+                ("openssl", vec![false, false]),
+                ("serde", vec![true, true]),
+                ("windows-sys", vec![false, true]),
+            ]
+        );
+        assert_eq!(rows[0].applies[0].triple, "x86_64-unknown-linux-musl");
+    }
+}
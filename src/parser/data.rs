@@ -1,30 +1,186 @@
+use super::{cfg::TargetMatch, overrides::Override, source::Source};
+use crate::cargo_config::Registry;
 use hashbrown::HashMap;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct OutputRow {
     pub package_name: String,
+    /// The name of the matched crate, which differs from the searched name for patterns.
+    pub dep_name: String,
+    /// The dependency table the match came from, `None` for rows read from a Cargo.lock.
+    pub kind: Option<DependencyKind>,
+    /// The `[target.<cfg>]` expression of the table the match came from, if any.
+    pub target: Option<String>,
+    /// The version requirement for manifest rows, the exact version for lock rows.
     pub dep_version: String,
     pub path: String,
+    /// Whether the version was inherited from the workspace root (`workspace = true`).
+    pub inherited: bool,
+    /// The name the dependency is imported as, when renamed through `package = "..."`.
+    pub alias: Option<String>,
+    /// Where the dependency comes from, `None` for unresolved workspace inheritance.
+    pub source: Option<Source>,
+    pub checksum: Option<String>,
+    /// Whether the version could not be compared to the semver filter, e.g. for a git
+    /// dependency without a version requirement.
+    pub unevaluated: bool,
+    /// The package a manifest dependency resolved to in the project's Cargo.lock.
+    pub locked: Option<Locked>,
+    /// The features enabled on the dependency, empty for lock rows.
+    pub features: Vec<String>,
+    /// Whether the default features of the dependency are enabled, `None` for lock rows.
+    pub default_features: Option<bool>,
+    pub optional: bool,
+    /// The directory of the workspace root the project belongs to, if any.
+    pub workspace: Option<String>,
+    /// The `[patch]` or `[replace]` entry cargo builds instead of this dependency.
+    pub overridden: Option<Override>,
+    /// The registry the dependency comes from, as named in `.cargo/config.toml`.
+    pub registry: Option<Registry>,
+    /// Whether the dependency applies to each target given with `--target`, empty
+    /// without them and for lock rows.
+    pub applies: Vec<TargetMatch>,
 }
 
-#[derive(Debug, Deserialize)]
+impl OutputRow {
+    /// Whether the dependency is declared with the given feature, either a plain feature
+    /// name or `crate/feature` to only accept the named crate. The `default` feature is
+    /// enabled unless `default-features = false`.
+    pub fn enables(&self, feature: &str) -> bool {
+        let feature = match feature.split_once('/') {
+            Some((crate_name, _)) if crate_name != self.dep_name => return false,
+            Some((_, feature)) => feature,
+            None => feature,
+        };
+        self.features.iter().any(|enabled| enabled == feature)
+            || (feature == "default" && self.default_features == Some(true))
+    }
+}
+
+/// A locked package, as paired with the manifest dependency it was resolved from.
+#[derive(Debug, Clone, Serialize)]
+pub struct Locked {
+    pub version: String,
+    pub source: Option<Source>,
+    pub checksum: Option<String>,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyKind::Normal => write!(f, "normal"),
+            DependencyKind::Dev => write!(f, "dev"),
+            DependencyKind::Build => write!(f, "build"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
 pub enum Dependency {
     Simple(String),
     Detailed(DependencyDetails),
 }
 
-#[derive(Debug, Deserialize)]
+impl Dependency {
+    /// Resolves a `workspace = true` dependency against the `[workspace.dependencies]` entry
+    /// it inherits from. As with cargo, features declared by the member are added to the
+    /// inherited ones and `optional` is the member's own, while `default-features` can only
+    /// turn default features on: a member disabling them is ignored with a warning unless
+    /// the workspace entry disables them too.
+    pub fn inherit(self, name: &str, from: &Dependency) -> Dependency {
+        let Dependency::Detailed(details) = self else {
+            return self;
+        };
+        let mut inherited = match from.clone() {
+            Dependency::Simple(version) => DependencyDetails {
+                version: Some(version),
+                ..Default::default()
+            },
+            Dependency::Detailed(from) => from,
+        };
+        if let Some(features) = details.features {
+            inherited
+                .features
+                .get_or_insert_with(Vec::new)
+                .extend(features);
+        }
+        inherited.optional = details.optional.or(inherited.optional);
+        match (details.default_features, inherited.default_features) {
+            (Some(true), Some(false)) => inherited.default_features = Some(true),
+            (Some(false), None | Some(true)) => eprintln!(
+                "Ignoring default-features = false of {name}, which the workspace dependency enables"
+            ),
+            _ => {}
+        }
+        inherited.workspace = None;
+        Dependency::Detailed(inherited)
+    }
+
+    /// The name of the crate on its registry, which differs from the dependency key
+    /// when it was renamed through `package = "..."`.
+    pub fn crate_name<'a>(&'a self, key: &'a str) -> &'a str {
+        match self {
+            Dependency::Detailed(DependencyDetails {
+                package: Some(package),
+                ..
+            }) => package,
+            _ => key,
+        }
+    }
+
+    pub fn is_inherited(&self) -> bool {
+        matches!(self, Dependency::Detailed(details) if details.workspace == Some(true))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct DependencyDetails {
     pub version: Option<String>,
     pub features: Option<Vec<String>>,
+    pub workspace: Option<bool>,
+    pub package: Option<String>,
+    pub path: Option<String>,
+    pub git: Option<String>,
+    pub branch: Option<String>,
+    pub tag: Option<String>,
+    pub rev: Option<String>,
+    pub registry: Option<String>,
+    #[serde(rename = "default-features", alias = "default_features")]
+    pub default_features: Option<bool>,
+    pub optional: Option<bool>,
 }
 
+impl DependencyDetails {
+    /// The git reference the repository is requested at, written as in lockfile
+    /// sources: `branch=main`, `tag=v1.0` or `rev=a1b2c3`.
+    pub fn git_reference(&self) -> Option<String> {
+        [
+            ("branch", &self.branch),
+            ("tag", &self.tag),
+            ("rev", &self.rev),
+        ]
+        .into_iter()
+        .find_map(|(key, value)| value.as_ref().map(|value| format!("{key}={value}")))
+    }
+}
+
+/// The `[package]` table of a manifest. Only the name is read, since every other field
+/// may be inherited from the workspace (`version.workspace = true`).
 #[derive(Debug, Deserialize)]
-pub struct Package {
+pub struct ManifestPackage {
     pub name: String,
-    pub version: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -32,6 +188,8 @@ pub struct Target {
     #[serde(rename = "dev-dependencies")]
     pub dev_dependencies: Option<HashMap<String, Dependency>>,
     pub dependencies: Option<HashMap<String, Dependency>>,
+    #[serde(rename = "build-dependencies")]
+    pub build_dependencies: Option<HashMap<String, Dependency>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -40,24 +198,64 @@ pub struct Targets {
     pub targets: Option<HashMap<String, Target>>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Workspace {
+    pub members: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub dependencies: Option<HashMap<String, Dependency>>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CTomlFile {
-    pub package: Option<Package>,
+    pub package: Option<ManifestPackage>,
     pub dependencies: Option<HashMap<String, Dependency>>,
     #[serde(rename = "dev-dependencies")]
     pub dev_dependencies: Option<HashMap<String, Dependency>>,
+    #[serde(rename = "build-dependencies")]
+    pub build_dependencies: Option<HashMap<String, Dependency>>,
     #[serde(rename = "target")]
     pub target: Option<Targets>,
+    pub workspace: Option<Workspace>,
+    /// `[patch.<source>]` tables, keyed by the patched source.
+    pub patch: Option<HashMap<String, HashMap<String, Dependency>>>,
+    /// The legacy `[replace]` table, keyed by package id such as `"serde:1.0.217"`.
+    pub replace: Option<HashMap<String, Dependency>>,
+}
+
+/// A `[[package]]` entry of a Cargo.lock.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LockPackage {
+    pub name: String,
+    pub version: String,
+    /// Where the package comes from, absent for workspace members and path dependencies.
+    pub source: Option<String>,
+    pub checksum: Option<String>,
+    /// The packages this one depends on, as `name`, `name version` or `name version (source)`.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CLockFile {
-    pub package: Vec<Package>,
+    /// The lockfile format version, only written from version 3 onwards.
+    pub version: Option<u32>,
+    /// The root package of version 1 lockfiles written by very old cargo releases.
+    pub root: Option<LockPackage>,
+    #[serde(default)]
+    pub package: Vec<LockPackage>,
+    /// Checksums of version 1 lockfiles, keyed by `checksum name version (source)`.
+    pub metadata: Option<HashMap<String, String>>,
 }
 
 #[cfg(test)]
 mod test {
-    use crate::parser::data::{CLockFile, CTomlFile};
+    use crate::{
+        matcher::DependencyMatcher,
+        parser::{
+            data::{CLockFile, CTomlFile, Dependency},
+            FileParser,
+        },
+    };
 
     #[test]
     fn deserialize_simple() {
@@ -149,6 +347,27 @@ mod test {
             .is_some_and(|t| t.get("cfg(target_arch = \"wasm32\")").is_some()));
     }
 
+    #[test]
+    fn deserialize_build_dependencies() {
+        let toml_str = r#"
+        [build-dependencies]
+        bindgen = "0.71"
+
+        [target.'cfg(unix)'.build-dependencies]
+        cc = "1.0"
+        "#;
+
+        let toml: CTomlFile = toml::from_str(toml_str).unwrap();
+        assert!(toml
+            .build_dependencies
+            .is_some_and(|deps| deps.contains_key("bindgen")));
+        let targets = toml.target.unwrap().targets.unwrap();
+        assert!(targets["cfg(unix)"]
+            .build_dependencies
+            .as_ref()
+            .is_some_and(|deps| deps.contains_key("cc")));
+    }
+
     #[test]
     fn deserialize_lock() {
         let toml_str = r#"
@@ -180,4 +399,144 @@ mod test {
         let lock: CLockFile = toml::from_str(toml_str).unwrap();
         assert!(lock.package.len() == 3);
     }
+
+    #[test]
+    fn deserialize_workspace_inheritance() {
+        let root = r#"
+        [workspace]
+        members = ["member"]
+
+        [workspace.dependencies]
+        serde = { version = "1.0.217", features = ["derive"] }
+        toml = "0.8"
+        "#;
+
+        let member = r#"
+        [package]
+        name = "member"
+        version.workspace = true
+        edition.workspace = true
+
+        [dependencies]
+        serde = { workspace = true, features = ["rc"] }
+        toml.workspace = true
+        "#;
+
+        let root: CTomlFile = toml::from_str(root).unwrap();
+        let member: CTomlFile = toml::from_str(member).unwrap();
+        assert_eq!(member.package.unwrap().name, "member");
+
+        let workspace_deps = root.workspace.unwrap().dependencies.unwrap();
+        let mut deps = member.dependencies.unwrap();
+
+        let serde = deps.remove("serde").unwrap();
+        assert!(serde.is_inherited());
+        match serde.inherit("serde", &workspace_deps["serde"]) {
+            Dependency::Detailed(details) => {
+                assert_eq!(details.version.as_deref(), Some("1.0.217"));
+                assert_eq!(details.features.unwrap(), vec!["derive", "rc"]);
+            }
+            Dependency::Simple(_) => panic!("expected detailed dependency"),
+        }
+
+        let toml = deps.remove("toml").unwrap();
+        assert!(toml.is_inherited());
+        match toml.inherit("toml", &workspace_deps["toml"]) {
+            Dependency::Detailed(details) => assert_eq!(details.version.as_deref(), Some("0.8")),
+            Dependency::Simple(_) => panic!("expected detailed dependency"),
+        }
+    }
+
+    #[test]
+    fn git_references() {
+        let reference = |toml_str: &str| match toml::from_str::<Dependency>(toml_str).unwrap() {
+            Dependency::Detailed(details) => details.git_reference(),
+            Dependency::Simple(_) => panic!("expected detailed dependency"),
+        };
+        let url = r#"git = "https://github.com/me/dep""#;
+        assert_eq!(
+            reference(&format!("{url}\nbranch = \"main\"")).as_deref(),
+            Some("branch=main")
+        );
+        assert_eq!(
+            reference(&format!("{url}\ntag = \"v1.0\"")).as_deref(),
+            Some("tag=v1.0")
+        );
+        assert_eq!(
+            reference(&format!("{url}\nrev = \"a1b2c3\"")).as_deref(),
+            Some("rev=a1b2c3")
+        );
+        assert_eq!(reference(url), None);
+    }
+
+    #[test]
+    fn inherit_features() {
+        let member = |toml_str: &str| toml::from_str::<Dependency>(toml_str).unwrap();
+        let details = |dependency: Dependency| match dependency {
+            Dependency::Detailed(details) => details,
+            Dependency::Simple(_) => panic!("expected detailed dependency"),
+        };
+        let workspace = member(
+            r#"
+            version = "1.0"
+            features = ["derive"]
+            default-features = false
+            "#,
+        );
+
+        let inherited = details(
+            member(
+                r#"
+                workspace = true
+                features = ["rc"]
+                optional = true
+                "#,
+            )
+            .inherit("serde", &workspace),
+        );
+        assert_eq!(inherited.version.as_deref(), Some("1.0"));
+        assert_eq!(
+            inherited.features,
+            Some(vec!["derive".to_string(), "rc".to_string()])
+        );
+        assert_eq!(inherited.optional, Some(true));
+        assert_eq!(inherited.default_features, Some(false));
+        assert_eq!(inherited.workspace, None);
+
+        // Members can turn default features back on, but not off.
+        let enabled = member("workspace = true\ndefault-features = true");
+        assert_eq!(
+            details(enabled.inherit("serde", &workspace)).default_features,
+            Some(true)
+        );
+        let disabled = member("workspace = true\ndefault-features = false");
+        assert_eq!(
+            details(disabled.inherit("serde", &Dependency::Simple("1.0".to_string())))
+                .default_features,
+            None
+        );
+    }
+
+    #[test]
+    fn enabled_features() {
+        let toml_content = r#"
+        [dependencies]
+        serde = { version = "1.0", features = ["derive"] }
+        tokio = { version = "1", default-features = false }
+        "#;
+        let matcher = DependencyMatcher::new(&[], &[]).unwrap();
+        let rows = FileParser::new().parse_toml(toml_content, &matcher, "Cargo.toml", None);
+        let row = |name: &str| rows.iter().find(|row| row.dep_name == name).unwrap();
+
+        let serde = row("serde");
+        assert!(serde.enables("derive"));
+        assert!(serde.enables("serde/derive"));
+        assert!(!serde.enables("tokio/derive"));
+        assert!(!serde.enables("rc"));
+        assert!(serde.enables("default"));
+
+        let tokio = row("tokio");
+        assert!(!tokio.enables("default"));
+        assert!(!tokio.enables("tokio/default"));
+    }
 }
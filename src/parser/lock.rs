@@ -0,0 +1,450 @@
+use super::data::{CLockFile, LockPackage};
+use hashbrown::HashMap;
+use std::collections::VecDeque;
+
+/// Upper bound on the chains enumerated between two packages, since the number of
+/// paths in a large dependency graph grows exponentially.
+const MAX_PATHS: usize = 1000;
+/// Upper bound on the packages visited while enumerating those chains, bounding the work
+/// done as well as the chains found.
+const MAX_VISITS: usize = 1_000_000;
+
+/// Prefix of the checksum keys in the `[metadata]` table of version 1 lockfiles.
+const METADATA_CHECKSUM: &str = "checksum ";
+/// Placeholder version 1 lockfiles use for packages without a checksum.
+const NO_CHECKSUM: &str = "<none>";
+
+impl CLockFile {
+    /// Every locked package, with the checksums of version 1 lockfiles moved from the
+    /// `[metadata]` table to their package.
+    pub fn into_packages(self) -> Vec<LockPackage> {
+        let mut packages = self.package;
+        packages.extend(self.root);
+
+        if let Some(metadata) = self.metadata {
+            for (key, checksum) in metadata {
+                let Some(reference) = key.strip_prefix(METADATA_CHECKSUM) else {
+                    continue;
+                };
+                if checksum == NO_CHECKSUM {
+                    continue;
+                }
+                let reference = PackageRef::parse(reference);
+                if let Some(package) = packages
+                    .iter_mut()
+                    .find(|package| reference.matches(package))
+                {
+                    package.checksum.get_or_insert(checksum);
+                }
+            }
+        }
+        packages
+    }
+}
+
+/// A reference to a locked package, as written in `dependencies` lists and in the
+/// `[metadata]` keys: `name`, `name version` or `name version (source)`. Cargo only
+/// writes as much as needed to tell apart several packages sharing a name.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PackageRef<'a> {
+    pub name: &'a str,
+    pub version: Option<&'a str>,
+    pub source: Option<&'a str>,
+}
+
+impl<'a> PackageRef<'a> {
+    pub fn parse(reference: &'a str) -> Self {
+        let mut parts = reference.trim().splitn(3, ' ');
+        PackageRef {
+            name: parts.next().unwrap_or_default(),
+            version: parts.next(),
+            source: parts
+                .next()
+                .map(|source| source.trim_start_matches('(').trim_end_matches(')')),
+        }
+    }
+
+    pub fn matches(&self, package: &LockPackage) -> bool {
+        package.name == self.name
+            && self
+                .version
+                .is_none_or(|version| package.version == version)
+            && self
+                .source
+                .is_none_or(|source| package.source.as_deref() == Some(source))
+    }
+}
+
+/// Resolves package references to the index of the package they point to.
+pub struct LockResolver<'a> {
+    packages: &'a [LockPackage],
+    by_name: HashMap<&'a str, Vec<usize>>,
+}
+
+impl<'a> LockResolver<'a> {
+    pub fn new(packages: &'a [LockPackage]) -> Self {
+        let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (id, package) in packages.iter().enumerate() {
+            by_name.entry(package.name.as_str()).or_default().push(id);
+        }
+        LockResolver { packages, by_name }
+    }
+
+    /// The package a reference points to, `None` when no package matches it or when it is
+    /// ambiguous, which only happens in hand-edited lockfiles.
+    pub fn resolve(&self, reference: &str) -> Option<usize> {
+        let reference = PackageRef::parse(reference);
+        let mut candidates = self
+            .by_name
+            .get(reference.name)?
+            .iter()
+            .filter(|&&id| reference.matches(&self.packages[id]));
+
+        match (candidates.next(), candidates.next()) {
+            (Some(&id), None) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+/// The dependency graph of a Cargo.lock, packages being identified by their index.
+pub struct LockGraph {
+    pub packages: Vec<LockPackage>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl LockGraph {
+    pub fn new(lock: CLockFile) -> Self {
+        let packages = lock.into_packages();
+        let edges = resolve_edges(&packages);
+        LockGraph { packages, edges }
+    }
+
+    /// Every locked package whose name satisfies the predicate.
+    pub fn find<'a>(
+        &'a self,
+        matches: impl Fn(&str) -> bool + 'a,
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.packages
+            .iter()
+            .enumerate()
+            .filter(move |(_, package)| matches(&package.name))
+            .map(|(id, _)| id)
+    }
+
+    /// Packages without a source, which are the workspace members and path dependencies.
+    pub fn roots(&self) -> impl Iterator<Item = usize> + '_ {
+        self.packages
+            .iter()
+            .enumerate()
+            .filter(|(_, package)| package.source.is_none())
+            .map(|(id, _)| id)
+    }
+
+    /// The shortest chain of dependencies leading from `from` to `to`, both included.
+    pub fn shortest_path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let mut parents = vec![None; self.packages.len()];
+        let mut queue = VecDeque::from([from]);
+        let mut seen = vec![false; self.packages.len()];
+        seen[from] = true;
+
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![to];
+                while let Some(parent) = parents[*path.last()?] {
+                    path.push(parent);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &next in &self.edges[current] {
+                if !seen[next] {
+                    seen[next] = true;
+                    parents[next] = Some(current);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Every chain of dependencies leading from `from` to `to`, shortest first.
+    pub fn all_paths(&self, from: usize, to: usize) -> Vec<Vec<usize>> {
+        let mut search = PathSearch {
+            graph: self,
+            to,
+            reaches: self.reaching(to),
+            on_path: vec![false; self.packages.len()],
+            path: vec![from],
+            paths: Vec::new(),
+            visits: 0,
+        };
+        if search.reaches[from] {
+            search.collect(from);
+        }
+        if search.paths.len() >= MAX_PATHS || search.visits >= MAX_VISITS {
+            eprintln!(
+                "Too many dependency chains from {} to {}, showing the first {}",
+                self.packages[from].name,
+                self.packages[to].name,
+                search.paths.len()
+            );
+        }
+        let mut paths = search.paths;
+        paths.sort_by_key(Vec::len);
+        paths
+    }
+
+    /// Marks the packages from which `to` can be reached, `to` included.
+    fn reaching(&self, to: usize) -> Vec<bool> {
+        let mut dependents = vec![Vec::new(); self.packages.len()];
+        for (package, edges) in self.edges.iter().enumerate() {
+            for &dependency in edges {
+                dependents[dependency].push(package);
+            }
+        }
+
+        let mut reaches = vec![false; self.packages.len()];
+        reaches[to] = true;
+        let mut queue = VecDeque::from([to]);
+        while let Some(current) = queue.pop_front() {
+            for &dependent in &dependents[current] {
+                if !reaches[dependent] {
+                    reaches[dependent] = true;
+                    queue.push_back(dependent);
+                }
+            }
+        }
+        reaches
+    }
+}
+
+/// The packages each package depends on, by index.
+fn resolve_edges(packages: &[LockPackage]) -> Vec<Vec<usize>> {
+    let resolver = LockResolver::new(packages);
+    packages
+        .iter()
+        .map(|package| {
+            package
+                .dependencies
+                .iter()
+                .filter_map(|reference| {
+                    let resolved = resolver.resolve(reference);
+                    if resolved.is_none() {
+                        eprintln!(
+                            "Unresolved dependency of {} {}: {reference}",
+                            package.name, package.version
+                        );
+                    }
+                    resolved
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// State of the depth-first enumeration of the chains leading to a package, which only
+/// descends into packages the target can be reached from.
+struct PathSearch<'a> {
+    graph: &'a LockGraph,
+    to: usize,
+    reaches: Vec<bool>,
+    on_path: Vec<bool>,
+    path: Vec<usize>,
+    paths: Vec<Vec<usize>>,
+    visits: usize,
+}
+
+impl PathSearch<'_> {
+    fn collect(&mut self, current: usize) {
+        if self.paths.len() >= MAX_PATHS || self.visits >= MAX_VISITS {
+            return;
+        }
+        self.visits += 1;
+        if current == self.to {
+            self.paths.push(self.path.clone());
+            return;
+        }
+        self.on_path[current] = true;
+        let graph = self.graph;
+        for &next in &graph.edges[current] {
+            if self.reaches[next] && !self.on_path[next] {
+                self.path.push(next);
+                self.collect(next);
+                self.path.pop();
+            }
+        }
+        self.on_path[current] = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LockGraph, LockResolver, PackageRef};
+    use crate::parser::data::CLockFile;
+
+    #[test]
+    fn find_dependency_chains() {
+        let toml_str = r#"
+        [[package]]
+        name = "app"
+        version = "0.1.0"
+        dependencies = [
+         "serde_json",
+         "syn 1.0.109",
+        ]
+
+        [[package]]
+        name = "serde_json"
+        version = "1.0.134"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+        dependencies = [
+         "serde_derive",
+        ]
+
+        [[package]]
+        name = "serde_derive"
+        version = "1.0.217"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+        dependencies = [
+         "syn 2.0.94",
+        ]
+
+        [[package]]
+        name = "syn"
+        version = "1.0.109"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+
+        [[package]]
+        name = "syn"
+        version = "2.0.94"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+        "#;
+
+        let lock: CLockFile = toml::from_str(toml_str).unwrap();
+        let graph = LockGraph::new(lock);
+        let roots: Vec<_> = graph.roots().collect();
+        assert_eq!(roots, vec![0]);
+
+        let syn: Vec<_> = graph.find(|name| name == "syn").collect();
+        assert_eq!(syn, vec![3, 4]);
+        assert_eq!(graph.shortest_path(0, 3), Some(vec![0, 3]));
+        assert_eq!(graph.shortest_path(0, 4), Some(vec![0, 1, 2, 4]));
+        assert_eq!(graph.all_paths(0, 4), vec![vec![0, 1, 2, 4]]);
+        assert_eq!(graph.shortest_path(3, 4), None);
+    }
+
+    #[test]
+    fn parse_package_refs() {
+        assert_eq!(
+            PackageRef::parse("syn"),
+            PackageRef {
+                name: "syn",
+                version: None,
+                source: None
+            }
+        );
+        assert_eq!(
+            PackageRef::parse(
+                "syn 1.0.109 (registry+https://github.com/rust-lang/crates.io-index)"
+            ),
+            PackageRef {
+                name: "syn",
+                version: Some("1.0.109"),
+                source: Some("registry+https://github.com/rust-lang/crates.io-index")
+            }
+        );
+    }
+
+    #[test]
+    fn skip_packages_not_leading_to_the_target() {
+        // 30 layers of 2 crates each depending on both crates of the next layer, which
+        // hold 2^30 chains none of which leads to `zz`.
+        let layer = |depth: usize| [format!("a{depth}"), format!("b{depth}")];
+        let mut toml_str = String::from(
+            "[[package]]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = [\"a0\", \"b0\", \"zz\"]\n",
+        );
+        for depth in 0..30 {
+            for name in layer(depth) {
+                toml_str.push_str(&format!(
+                    "[[package]]\nname = \"{name}\"\nversion = \"1.0.0\"\ndependencies = {:?}\n",
+                    if depth < 29 {
+                        layer(depth + 1).to_vec()
+                    } else {
+                        Vec::new()
+                    }
+                ));
+            }
+        }
+        toml_str.push_str("[[package]]\nname = \"zz\"\nversion = \"1.0.0\"\n");
+
+        let lock: CLockFile = toml::from_str(&toml_str).unwrap();
+        let graph = LockGraph::new(lock);
+        let app = graph.find(|name| name == "app").next().unwrap();
+        let zz = graph.find(|name| name == "zz").next().unwrap();
+        assert_eq!(graph.all_paths(app, zz), vec![vec![app, zz]]);
+    }
+
+    #[test]
+    fn resolve_v1_lock() {
+        let toml_str = r#"
+        [root]
+        name = "app"
+        version = "0.1.0"
+        dependencies = [
+         "libc 0.2.20 (registry+https://github.com/rust-lang/crates.io-index)",
+        ]
+
+        [[package]]
+        name = "libc"
+        version = "0.2.20"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+
+        [metadata]
+        "checksum libc 0.2.20 (registry+https://github.com/rust-lang/crates.io-index)" = "684f330624d8c3784fb9558ca46c4ce488073a8d22450415c5eb4f4cfb0d11b5"
+        "#;
+
+        let lock: CLockFile = toml::from_str(toml_str).unwrap();
+
+        let graph = LockGraph::new(lock);
+        let libc = graph.find(|name| name == "libc").next().unwrap();
+        assert_eq!(
+            graph.packages[libc].checksum.as_deref(),
+            Some("684f330624d8c3784fb9558ca46c4ce488073a8d22450415c5eb4f4cfb0d11b5")
+        );
+        let app = graph.find(|name| name == "app").next().unwrap();
+        assert_eq!(graph.shortest_path(app, libc), Some(vec![app, libc]));
+    }
+
+    #[test]
+    fn resolve_refs_sharing_name_and_version() {
+        let toml_str = r#"
+        version = 4
+
+        [[package]]
+        name = "app"
+        version = "0.1.0"
+        dependencies = [
+         "serde 1.0.217 (git+https://github.com/me/serde?branch=fork#deadbeef)",
+        ]
+
+        [[package]]
+        name = "serde"
+        version = "1.0.217"
+        source = "registry+https://github.com/rust-lang/crates.io-index"
+
+        [[package]]
+        name = "serde"
+        version = "1.0.217"
+        source = "git+https://github.com/me/serde?branch=fork#deadbeef"
+        "#;
+
+        let lock: CLockFile = toml::from_str(toml_str).unwrap();
+
+        let packages = lock.into_packages();
+        let resolver = LockResolver::new(&packages);
+        assert_eq!(resolver.resolve(&packages[0].dependencies[0]), Some(2));
+        assert_eq!(resolver.resolve("serde 1.0.217"), None);
+        assert_eq!(resolver.resolve("app"), Some(0));
+    }
+}
@@ -0,0 +1,199 @@
+use super::{
+    data::{CTomlFile, OutputRow},
+    source::{normalize_git_url, normalize_index, Source},
+};
+use semver::{Version, VersionReq};
+use serde::Serialize;
+use std::fmt;
+
+/// The key of the `[patch]` table overriding crates.io.
+const CRATES_IO: &str = "crates-io";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverrideKind {
+    /// A `[patch.<source>]` entry.
+    Patch,
+    /// A legacy `[replace]` entry.
+    Replace,
+}
+
+impl fmt::Display for OverrideKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverrideKind::Patch => write!(f, "patch"),
+            OverrideKind::Replace => write!(f, "replace"),
+        }
+    }
+}
+
+/// A crate overridden by a `[patch]` or `[replace]` entry, which cargo builds instead
+/// of the one the dependency requires.
+#[derive(Debug, Clone, Serialize)]
+pub struct Override {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub kind: OverrideKind,
+    /// The source whose crate is patched: `crates-io`, a registry name or a URL.
+    /// `None` for `[replace]` entries, which apply to every source.
+    pub patched: Option<String>,
+    /// The version a `[replace]` entry is restricted to, as in `"serde:1.0.217"`.
+    pub version: Option<String>,
+    /// Where the replacement crate comes from.
+    pub source: Option<Source>,
+    /// The manifest declaring the override.
+    pub path: String,
+}
+
+impl Override {
+    /// Whether the override applies to a dependency declared in a manifest.
+    pub fn applies_to(&self, row: &OutputRow) -> bool {
+        if row.kind.is_none() || row.dep_name != self.crate_name {
+            return false;
+        }
+        let source = match (&self.patched, &row.source) {
+            (None, _) => true,
+            (Some(patched), Some(source)) => is_patched(source, patched),
+            (Some(_), None) => false,
+        };
+        let version = match &self.version {
+            Some(version) => match (Version::parse(version), VersionReq::parse(&row.dep_version)) {
+                (Ok(version), Ok(requirement)) => requirement.matches(&version),
+                _ => false,
+            },
+            None => true,
+        };
+        source && version
+    }
+}
+
+/// Reads the `[patch.*]` and `[replace]` tables of a manifest.
+pub fn overrides(toml: &CTomlFile, path: &str) -> Vec<Override> {
+    let mut found = Vec::new();
+
+    for (patched, patches) in toml.patch.iter().flatten() {
+        found.extend(patches.iter().map(|(key, dependency)| Override {
+            crate_name: dependency.crate_name(key).to_string(),
+            kind: OverrideKind::Patch,
+            patched: Some(patched.clone()),
+            version: None,
+            source: Source::from_manifest(dependency),
+            path: path.to_string(),
+        }));
+    }
+    for (spec, dependency) in toml.replace.iter().flatten() {
+        let (crate_name, version) = match spec.split_once(':') {
+            Some((crate_name, version)) => (crate_name, Some(version.to_string())),
+            None => (spec.as_str(), None),
+        };
+        found.push(Override {
+            crate_name: crate_name.to_string(),
+            kind: OverrideKind::Replace,
+            patched: None,
+            version,
+            source: Source::from_manifest(dependency),
+            path: path.to_string(),
+        });
+    }
+    found
+}
+
+/// Flags every dependency an override applies to, the first matching override winning.
+pub fn apply(rows: &mut [OutputRow], overrides: &[Override]) {
+    for row in rows.iter_mut().filter(|row| row.overridden.is_none()) {
+        row.overridden = overrides
+            .iter()
+            .find(|candidate| candidate.applies_to(row))
+            .cloned();
+    }
+}
+
+/// Whether a `[patch.<patched>]` table applies to a dependency from the given source,
+/// alternate registries being patched either by name or by index URL.
+fn is_patched(source: &Source, patched: &str) -> bool {
+    match source {
+        Source::CratesIo => patched == CRATES_IO,
+        Source::Registry { name, index } => {
+            name.as_deref() == Some(patched)
+                || index
+                    .as_deref()
+                    .is_some_and(|index| normalize_index(index) == normalize_index(patched))
+        }
+        Source::Git { url, .. } => normalize_git_url(url) == normalize_git_url(patched),
+        Source::Path { .. } => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_patched, overrides, OverrideKind};
+    use crate::{
+        matcher::DependencyMatcher,
+        parser::{source::Source, FileParser},
+    };
+
+    #[test]
+    fn flag_overridden_dependencies() {
+        let toml_content = r#"
+        [dependencies]
+        serde = "1"
+        syn = { git = "https://github.com/dtolnay/syn" }
+        tokio = "1.40"
+
+        [patch.crates-io]
+        serde = { git = "https://github.com/me/serde", branch = "fork" }
+
+        [patch."https://github.com/dtolnay/syn.git"]
+        syn = { path = "../syn" }
+
+        [replace]
+        "tokio:1.38.0" = { path = "../tokio" }
+        "#;
+
+        let toml = toml::from_str(toml_content).unwrap();
+        let found = overrides(&toml, "Cargo.toml");
+        assert_eq!(found.len(), 3);
+
+        let matcher = DependencyMatcher::new(&[], &[]).unwrap();
+        let mut rows = FileParser::new().parse_toml(toml_content, &matcher, "Cargo.toml", None);
+        super::apply(&mut rows, &found);
+        rows.sort_by(|a, b| a.dep_name.cmp(&b.dep_name));
+
+        let flagged: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.dep_name.as_str(),
+                    row.overridden.as_ref().map(|overridden| overridden.kind),
+                )
+            })
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![
+                ("serde", Some(OverrideKind::Patch)),
+                ("syn", Some(OverrideKind::Patch)),
+                ("tokio", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn match_patched_registries() {
+        let registry = |name: Option<&str>, index: &str| Source::Registry {
+            name: name.map(str::to_string),
+            index: Some(index.to_string()),
+        };
+        let internal = registry(None, "registry+https://internal.example/index");
+
+        assert!(is_patched(&internal, "https://internal.example/index/"));
+        assert!(!is_patched(&internal, "index"));
+        assert!(!is_patched(&internal, "example/index"));
+        assert!(is_patched(
+            &registry(Some("internal"), "https://internal.example/index"),
+            "internal"
+        ));
+        assert!(is_patched(&Source::CratesIo, "crates-io"));
+        assert!(!is_patched(&Source::CratesIo, "internal"));
+    }
+}
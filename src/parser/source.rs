@@ -0,0 +1,268 @@
+use super::data::Dependency;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt;
+
+const CRATES_IO_INDEX: &str = "https://github.com/rust-lang/crates.io-index";
+const CRATES_IO_SPARSE_INDEX: &str = "sparse+https://index.crates.io/";
+
+/// Where a package comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Source {
+    #[serde(rename = "crates.io")]
+    CratesIo,
+    /// An alternate registry, named in manifests and identified by its index URL in lockfiles.
+    Registry {
+        name: Option<String>,
+        index: Option<String>,
+    },
+    Git {
+        url: String,
+        /// The `branch=`, `tag=` or `rev=` query the repository was requested with.
+        reference: Option<String>,
+        /// The commit the reference was locked to.
+        precise: Option<String>,
+    },
+    /// A local package, either a path dependency or a workspace member.
+    Path { path: Option<String> },
+}
+
+/// The kinds of [`Source`], to filter results by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SourceKind {
+    CratesIo,
+    /// Alternate registries.
+    Registry,
+    Git,
+    Path,
+}
+
+impl Source {
+    pub fn kind(&self) -> SourceKind {
+        match self {
+            Source::CratesIo => SourceKind::CratesIo,
+            Source::Registry { .. } => SourceKind::Registry,
+            Source::Git { .. } => SourceKind::Git,
+            Source::Path { .. } => SourceKind::Path,
+        }
+    }
+
+    /// Parses the `source` field of a Cargo.lock package, e.g.
+    /// `registry+https://github.com/rust-lang/crates.io-index` or
+    /// `git+https://github.com/serde-rs/serde?branch=master#a1b2c3`.
+    /// Packages without a source are local to the workspace.
+    pub fn from_lock(source: Option<&str>) -> Source {
+        let Some(source) = source else {
+            return Source::Path { path: None };
+        };
+
+        if let Some(index) = source.strip_prefix("registry+") {
+            return Source::registry(index);
+        }
+        if source.starts_with("sparse+") {
+            return Source::registry(source);
+        }
+        if let Some(git) = source.strip_prefix("git+") {
+            let (url, precise) = match git.split_once('#') {
+                Some((url, precise)) => (url, Some(precise.to_string())),
+                None => (git, None),
+            };
+            let (url, reference) = match url.split_once('?') {
+                Some((url, reference)) => (url, Some(reference.to_string())),
+                None => (url, None),
+            };
+            return Source::Git {
+                url: url.to_string(),
+                reference,
+                precise,
+            };
+        }
+        if let Some(path) = source.strip_prefix("path+") {
+            return Source::Path {
+                path: Some(path.trim_start_matches("file://").to_string()),
+            };
+        }
+        Source::Registry {
+            name: None,
+            index: Some(source.to_string()),
+        }
+    }
+
+    /// Where a manifest dependency comes from, `None` when it still inherits from a
+    /// workspace that could not be found. Local paths take precedence, as cargo only
+    /// uses the registry version of a path dependency once published.
+    pub fn from_manifest(dependency: &Dependency) -> Option<Source> {
+        let details = match dependency {
+            Dependency::Simple(_) => return Some(Source::CratesIo),
+            Dependency::Detailed(details) => details,
+        };
+        if dependency.is_inherited() {
+            return None;
+        }
+
+        if let Some(path) = &details.path {
+            return Some(Source::Path {
+                path: Some(path.clone()),
+            });
+        }
+        if let Some(url) = &details.git {
+            return Some(Source::Git {
+                url: url.clone(),
+                reference: details.git_reference(),
+                precise: None,
+            });
+        }
+        match &details.registry {
+            Some(name) => Some(Source::Registry {
+                name: Some(name.clone()),
+                index: None,
+            }),
+            None => Some(Source::CratesIo),
+        }
+    }
+
+    /// Whether a manifest dependency from this source may have been locked as a package
+    /// from `locked`. Registry names are not resolved to their index, so any alternate
+    /// registry is accepted.
+    pub fn may_lock(&self, locked: &Source) -> bool {
+        match (self, locked) {
+            (Source::CratesIo, Source::CratesIo) => true,
+            (Source::Registry { .. }, Source::Registry { .. }) => true,
+            (Source::Git { url, .. }, Source::Git { url: locked, .. }) => {
+                normalize_git_url(url) == normalize_git_url(locked)
+            }
+            (Source::Path { .. }, Source::Path { .. }) => true,
+            _ => false,
+        }
+    }
+
+    fn registry(index: &str) -> Source {
+        if index == CRATES_IO_INDEX || index == CRATES_IO_SPARSE_INDEX {
+            Source::CratesIo
+        } else {
+            Source::Registry {
+                name: None,
+                index: Some(index.to_string()),
+            }
+        }
+    }
+}
+
+/// Git URLs are equivalent with or without a trailing slash or `.git` suffix.
+pub fn normalize_git_url(url: &str) -> &str {
+    let url = url.trim_end_matches('/');
+    url.strip_suffix(".git").unwrap_or(url)
+}
+
+/// Index URLs are equivalent with or without their `registry+` prefix and trailing slash.
+pub fn normalize_index(index: &str) -> &str {
+    let index = index.strip_prefix("registry+").unwrap_or(index);
+    index.trim_end_matches('/')
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::CratesIo => write!(f, "crates.io"),
+            Source::Registry {
+                name: Some(name), ..
+            } => write!(f, "registry {name}"),
+            Source::Registry {
+                index: Some(index), ..
+            } => write!(f, "registry {index}"),
+            Source::Registry { .. } => write!(f, "registry"),
+            Source::Git { url, reference, .. } => match reference {
+                Some(reference) => write!(f, "git {url}?{reference}"),
+                None => write!(f, "git {url}"),
+            },
+            Source::Path { path: Some(path) } => write!(f, "path {path}"),
+            Source::Path { path: None } => write!(f, "path"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Source;
+    use crate::parser::data::Dependency;
+
+    #[test]
+    fn parse_lock_sources() {
+        assert_eq!(
+            Source::from_lock(Some(
+                "registry+https://github.com/rust-lang/crates.io-index"
+            )),
+            Source::CratesIo
+        );
+        assert_eq!(
+            Source::from_lock(Some("sparse+https://index.crates.io/")),
+            Source::CratesIo
+        );
+        assert_eq!(
+            Source::from_lock(Some("sparse+https://my-registry.example/index/")),
+            Source::Registry {
+                name: None,
+                index: Some("sparse+https://my-registry.example/index/".to_string())
+            }
+        );
+        assert_eq!(
+            Source::from_lock(Some(
+                "git+https://github.com/serde-rs/serde?branch=master#a1b2c3"
+            )),
+            Source::Git {
+                url: "https://github.com/serde-rs/serde".to_string(),
+                reference: Some("branch=master".to_string()),
+                precise: Some("a1b2c3".to_string()),
+            }
+        );
+        assert_eq!(Source::from_lock(None), Source::Path { path: None });
+    }
+
+    #[test]
+    fn parse_manifest_sources() {
+        let source = |toml_str: &str| {
+            let dependency: Dependency = toml::from_str::<toml::Table>(toml_str).unwrap()["dep"]
+                .clone()
+                .try_into()
+                .unwrap();
+            Source::from_manifest(&dependency)
+        };
+
+        assert_eq!(source(r#"dep = "1""#), Some(Source::CratesIo));
+        assert_eq!(
+            source(r#"dep = { version = "1", features = ["derive"] }"#),
+            Some(Source::CratesIo)
+        );
+        assert_eq!(
+            source(r#"dep = { version = "0.3", registry = "internal" }"#),
+            Some(Source::Registry {
+                name: Some("internal".to_string()),
+                index: None
+            })
+        );
+        assert_eq!(
+            source(r#"dep = { git = "https://github.com/me/dep", tag = "v1.0" }"#),
+            Some(Source::Git {
+                url: "https://github.com/me/dep".to_string(),
+                reference: Some("tag=v1.0".to_string()),
+                precise: None
+            })
+        );
+        assert_eq!(
+            source(r#"dep = { git = "https://github.com/me/dep" }"#),
+            Some(Source::Git {
+                url: "https://github.com/me/dep".to_string(),
+                reference: None,
+                precise: None
+            })
+        );
+        assert_eq!(
+            source(r#"dep = { version = "1", path = "../dep" }"#),
+            Some(Source::Path {
+                path: Some("../dep".to_string())
+            })
+        );
+        assert_eq!(source(r#"dep = { workspace = true }"#), None);
+    }
+}
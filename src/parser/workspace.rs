@@ -0,0 +1,299 @@
+use super::{
+    data::{CLockFile, CTomlFile, Dependency, LockPackage},
+    overrides::{self, Override},
+};
+use crate::file_explorer::{absolute, CLOCK};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use hashbrown::HashMap;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// A manifest declaring a `[workspace]`, along with the members it lists.
+#[derive(Debug)]
+pub struct WorkspaceRoot {
+    /// The directory of the root manifest.
+    pub dir: PathBuf,
+    /// The lockfile shared by every member, only set when lockfiles are read.
+    pub lock: Option<PathBuf>,
+    /// The packages of `lock`, parsed by the first member reading them.
+    locked: OnceLock<Vec<LockPackage>>,
+    /// The `[patch]` and `[replace]` entries of the root, which apply to every member.
+    pub overrides: Vec<Override>,
+    /// The `[workspace.dependencies]` members inherit from with `workspace = true`.
+    pub dependencies: HashMap<String, Dependency>,
+    members: GlobSet,
+    exclude: Vec<PathBuf>,
+}
+
+impl WorkspaceRoot {
+    /// Reads the `[workspace]` table of a manifest, `None` when it has none.
+    pub fn read(manifest: &Path, deep: bool) -> Option<WorkspaceRoot> {
+        let contents = fs::read_to_string(manifest).ok()?;
+        let toml = toml::from_str::<CTomlFile>(&contents).ok()?;
+        let overrides = overrides::overrides(&toml, &manifest.to_string_lossy());
+        let workspace = toml.workspace?;
+        let dir = manifest.parent()?.to_path_buf();
+
+        let mut members = GlobSetBuilder::new();
+        for member in workspace.members.iter().flatten() {
+            match GlobBuilder::new(normalize(member))
+                .literal_separator(true)
+                .build()
+            {
+                Ok(glob) => {
+                    members.add(glob);
+                }
+                Err(e) => eprintln!(
+                    "Invalid workspace member: {member} in {} | {e}",
+                    manifest.display()
+                ),
+            }
+        }
+        let members = members.build().unwrap_or_else(|e| {
+            eprintln!("Invalid workspace members in: {} | {e}", manifest.display());
+            GlobSet::empty()
+        });
+        let exclude = workspace
+            .exclude
+            .iter()
+            .flatten()
+            .map(|path| dir.join(normalize(path)))
+            .collect();
+        let lock = dir.join(CLOCK);
+
+        Some(WorkspaceRoot {
+            lock: (deep && lock.is_file()).then_some(lock),
+            locked: OnceLock::new(),
+            overrides,
+            dependencies: workspace.dependencies.unwrap_or_default(),
+            dir,
+            members,
+            exclude,
+        })
+    }
+
+    /// The packages of the shared lockfile, read once for all members.
+    pub fn locked_packages(&self) -> Option<&[LockPackage]> {
+        let lock = self.lock.as_ref()?;
+        let packages = self.locked.get_or_init(|| {
+            let parsed = fs::read_to_string(lock)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| {
+                    toml::from_str::<CLockFile>(&contents).map_err(|e| e.to_string())
+                });
+            match parsed {
+                Ok(parsed) => parsed.into_packages(),
+                Err(e) => {
+                    eprintln!("Unparseable file: {:?} {e}", lock);
+                    Vec::new()
+                }
+            }
+        });
+        Some(packages)
+    }
+
+    /// The root directory spelled from the same base as `manifest`, the manifest of one of
+    /// its packages as found by the explorer, so that it reads like the paths reported
+    /// next to it. The absolute directory is returned when the manifest is spelled too
+    /// briefly to reach the root.
+    pub fn dir_from(&self, manifest: &Path) -> PathBuf {
+        let Some(package_dir) = manifest.parent() else {
+            return self.dir.clone();
+        };
+        let Ok(relative) = absolute(package_dir)
+            .strip_prefix(&self.dir)
+            .map(Path::to_path_buf)
+        else {
+            return self.dir.clone();
+        };
+        let mut dir = package_dir.to_path_buf();
+        for _ in relative.components() {
+            if !matches!(dir.components().next_back(), Some(Component::Normal(_))) {
+                return self.dir.clone();
+            }
+            dir.pop();
+        }
+        if dir.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            dir
+        }
+    }
+
+    /// Whether the package in the given directory belongs to the workspace, being either
+    /// the root package or a member that is not excluded. Path dependencies, which cargo
+    /// also makes members, have to be listed in `members` to be recognised.
+    pub fn contains(&self, package_dir: &Path) -> bool {
+        if package_dir == self.dir {
+            return true;
+        }
+        let Ok(relative) = package_dir.strip_prefix(&self.dir) else {
+            return false;
+        };
+        !self
+            .exclude
+            .iter()
+            .any(|excluded| package_dir.starts_with(excluded))
+            && self.members.is_match(relative)
+    }
+}
+
+/// Member paths are relative to the root, e.g. `crates/*` or `./cli/`.
+fn normalize(path: &str) -> &str {
+    let path = path.trim_end_matches('/');
+    path.strip_prefix("./").unwrap_or(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::WorkspaceRoot;
+    use crate::{cli::Args, file_explorer::absolute, fixture};
+    use clap::Parser;
+    use globset::{Glob, GlobSetBuilder};
+    use hashbrown::HashMap;
+    use std::{
+        path::{Path, PathBuf},
+        sync::OnceLock,
+    };
+
+    #[test]
+    fn recognise_members() {
+        let mut members = GlobSetBuilder::new();
+        members.add(Glob::new("crates/*").unwrap());
+        members.add(Glob::new("cli").unwrap());
+        let root = WorkspaceRoot {
+            dir: PathBuf::from("/ws"),
+            lock: None,
+            locked: OnceLock::new(),
+            overrides: Vec::new(),
+            dependencies: HashMap::new(),
+            members: members.build().unwrap(),
+            exclude: vec![PathBuf::from("/ws/crates/legacy")],
+        };
+
+        assert!(root.contains(Path::new("/ws")));
+        assert!(root.contains(Path::new("/ws/cli")));
+        assert!(root.contains(Path::new("/ws/crates/core")));
+        assert!(!root.contains(Path::new("/ws/crates/legacy")));
+        assert!(!root.contains(Path::new("/ws/examples/demo")));
+        assert!(!root.contains(Path::new("/other/cli")));
+    }
+
+    #[test]
+    fn spell_the_root_like_its_members() {
+        let root = |dir: PathBuf| WorkspaceRoot {
+            dir,
+            lock: None,
+            locked: OnceLock::new(),
+            overrides: Vec::new(),
+            dependencies: HashMap::new(),
+            members: GlobSetBuilder::new().build().unwrap(),
+            exclude: Vec::new(),
+        };
+        let dir_from = |dir: PathBuf, manifest: &str| root(dir).dir_from(Path::new(manifest));
+
+        assert_eq!(
+            dir_from(PathBuf::from("/ws"), "/ws/crates/core/Cargo.toml"),
+            Path::new("/ws")
+        );
+        let ws = absolute(Path::new("ws"));
+        assert_eq!(
+            dir_from(ws.clone(), "./ws/crates/core/Cargo.toml"),
+            Path::new("./ws")
+        );
+        assert_eq!(dir_from(ws.clone(), "ws/Cargo.toml"), Path::new("ws"));
+        assert_eq!(dir_from(ws.clone(), "crates/../Cargo.toml"), ws);
+        let cwd = absolute(Path::new("."));
+        assert_eq!(dir_from(cwd.clone(), "core/Cargo.toml"), Path::new("."));
+        assert_eq!(dir_from(cwd, "./Cargo.toml"), Path::new("."));
+    }
+
+    #[test]
+    fn read_workspace_from_disk() {
+        let fixture = fixture::dir(&[
+            (
+                "Cargo.toml",
+                r#"
+                [workspace]
+                members = ["crates/*"]
+                exclude = ["crates/legacy"]
+
+                [workspace.dependencies]
+                serde = "1.0"
+                "#,
+            ),
+            (
+                "Cargo.lock",
+                r#"
+                version = 4
+
+                [[package]]
+                name = "core"
+                version = "0.1.0"
+                dependencies = ["serde"]
+
+                [[package]]
+                name = "serde"
+                version = "1.0.217"
+                source = "registry+https://github.com/rust-lang/crates.io-index"
+                "#,
+            ),
+            (
+                "crates/core/Cargo.toml",
+                r#"
+                [package]
+                name = "core"
+
+                [dependencies]
+                serde.workspace = true
+                "#,
+            ),
+            (
+                "crates/legacy/Cargo.toml",
+                r#"
+                [package]
+                name = "legacy"
+
+                [dependencies]
+                serde = "1"
+                "#,
+            ),
+        ]);
+        let dir = fixture.path();
+
+        let root = WorkspaceRoot::read(&dir.join("Cargo.toml"), true).unwrap();
+        assert_eq!(root.lock, Some(dir.join("Cargo.lock")));
+        assert!(root.dependencies.contains_key("serde"));
+        assert!(root.contains(&dir.join("crates/core")));
+        assert!(!root.contains(&dir.join("crates/legacy")));
+
+        // The search root is spelled with `..` on purpose.
+        let mut args = Args::parse_from(["mole", "serde", "--deep", "--no-ignore"]);
+        args.path = dir.join("crates").join("..");
+        let rows = crate::search(&args).unwrap();
+
+        let found: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.package_name.as_str(),
+                    row.inherited,
+                    row.locked.as_ref().map(|locked| locked.version.as_str()),
+                    row.workspace.is_some(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("legacy", false, None, false),
+                // The root reports the lockfile it shares, members only their own entries.
+                ("-", false, None, true),
+                ("core", true, Some("1.0.217"), true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{
+    cli::Args,
+    error::MoleError,
+    file_explorer,
+    parser::{overrides::Override, FileParser},
+};
+use std::fs;
+
+/// A project overriding a crate through `[patch]` or `[replace]`.
+#[derive(Debug, Clone)]
+pub struct Patch {
+    pub package_name: String,
+    pub overridden: Override,
+}
+
+/// Finds the `[patch]` and `[replace]` entries of every manifest under `args.path`, only
+/// looking at the crates matching `args.name` and `args.regex` when given.
+pub fn patches(args: &Args) -> Result<Vec<Patch>, MoleError> {
+    let matcher = args.matcher()?;
+    let parser = FileParser::new();
+    let mut found = Vec::new();
+
+    for (_, package) in file_explorer::explore(args)? {
+        let Some(ref toml) = package.ctoml else {
+            continue;
+        };
+        let contents = fs::read_to_string(toml)?;
+        let package_name = parser.parse_name(&contents);
+        found.extend(
+            parser
+                .parse_overrides(&contents, &toml.to_string_lossy())
+                .into_iter()
+                .filter(|overridden| matcher.matches(&overridden.crate_name))
+                .map(|overridden| Patch {
+                    package_name: package_name.clone(),
+                    overridden,
+                }),
+        );
+    }
+    found.sort_by(|a, b| {
+        a.overridden
+            .path
+            .cmp(&b.overridden.path)
+            .then(a.overridden.crate_name.cmp(&b.overridden.crate_name))
+    });
+    Ok(found)
+}
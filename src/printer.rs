@@ -1,17 +1,44 @@
-use crate::parser::data::OutputRow;
+use crate::{
+    cli::GroupBy,
+    duplicates::Duplicate,
+    parser::{
+        cfg::TargetMatch,
+        data::OutputRow,
+        overrides::{Override, OverrideKind},
+    },
+    patches::Patch,
+    stale::StaleLock,
+    why::DependencyChain,
+};
 use clap::ValueEnum;
 use hashbrown::HashMap;
-use json::{print_json, DepInfo};
-use pretty_table::print_table;
+use json::{print_json, ChainInfo, CrateDuplicate, DepInfo, ProjectDuplicate, StaleInfo};
+use ndjson::{
+    print_ndjson, print_ndjson_lines, NdjsonChain, NdjsonDuplicate, NdjsonPatch, NdjsonStale,
+};
+use pretty_table::{
+    print_table, Row, CHAIN_HEADERS, CRATE_DUPLICATE_HEADERS, HEADERS, PATCH_HEADERS,
+    PROJECT_DUPLICATE_HEADERS, STALE_HEADERS,
+};
 use std::str::FromStr;
 
 pub mod json;
+pub mod ndjson;
 pub mod pretty_table;
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum PrintFormat {
     Table,
     Json,
+    /// Newline-delimited JSON, one row per line, printed as soon as each project is parsed.
+    Ndjson,
+}
+
+impl PrintFormat {
+    /// Whether rows can be printed as they are found instead of once the search is complete.
+    pub fn is_streamed(&self) -> bool {
+        matches!(self, PrintFormat::Ndjson)
+    }
 }
 
 impl FromStr for PrintFormat {
@@ -21,6 +48,7 @@ impl FromStr for PrintFormat {
         match s.to_lowercase().as_str() {
             "table" => Ok(PrintFormat::Table),
             "json" => Ok(PrintFormat::Json),
+            "ndjson" => Ok(PrintFormat::Ndjson),
             _ => Err(format!("Invalid value: {}", s)),
         }
     }
@@ -29,21 +57,363 @@ impl FromStr for PrintFormat {
 pub fn print(rows: Vec<OutputRow>, format: &PrintFormat) {
     match format {
         PrintFormat::Table => {
-            let rows = rows
-                .into_iter()
-                .map(|package| [package.package_name, package.dep_version, package.path])
-                .collect();
-            print_table(rows);
+            let (unevaluated, rows): (Vec<_>, Vec<_>) =
+                rows.into_iter().partition(|row| row.unevaluated);
+            print_table(&HEADERS, table_rows(rows));
+            if !unevaluated.is_empty() {
+                println!("\nNot evaluated against the filter:");
+                print_table(&HEADERS, table_rows(unevaluated));
+            }
         }
         PrintFormat::Json => {
             let mut mapped: HashMap<String, Vec<DepInfo>> = HashMap::new();
             rows.into_iter().for_each(|row| {
-                mapped.entry(row.package_name).or_default().push(DepInfo {
-                    version: row.dep_version,
-                    path: row.path,
-                });
+                mapped
+                    .entry(row.package_name.clone())
+                    .or_default()
+                    .push(DepInfo::from(row));
+            });
+            print_json(mapped);
+        }
+        PrintFormat::Ndjson => {
+            print_ndjson(rows);
+        }
+    }
+}
+
+fn table_rows(rows: Vec<OutputRow>) -> Vec<Row> {
+    rows.into_iter()
+        .map(|package| {
+            let version = if package.inherited {
+                format!("{} (workspace)", package.dep_version)
+            } else {
+                package.dep_version
+            };
+            let kind = match package.kind {
+                Some(kind) if package.optional => format!("{kind} (optional)"),
+                Some(kind) => kind.to_string(),
+                None => "lock".to_string(),
+            };
+            let mut features = package.features;
+            if package.default_features == Some(false) {
+                features.insert(0, "no-default-features".to_string());
+            }
+            let features = if features.is_empty() {
+                "-".to_string()
+            } else {
+                features.join(", ")
+            };
+            let dependency = match package.alias {
+                Some(alias) => format!("{} (as {alias})", package.dep_name),
+                None => package.dep_name,
+            };
+            let target = target_cell(package.target, &package.applies);
+            let mut source = package
+                .source
+                .map_or_else(|| "-".to_string(), |source| source.to_string());
+            if let Some(replaced_with) =
+                package.registry.and_then(|registry| registry.replaced_with)
+            {
+                source = format!("{source} (via {replaced_with})");
+            }
+            if let Some(overridden) = package.overridden {
+                let by = overridden
+                    .source
+                    .map_or_else(|| "-".to_string(), |source| source.to_string());
+                let verb = match overridden.kind {
+                    OverrideKind::Patch => "patched",
+                    OverrideKind::Replace => "replaced",
+                };
+                source = format!("{source} ({verb} by {by})");
+            }
+            // Manifest rows carry the checksum of the version they are locked to.
+            let (locked, checksum) = match package.locked {
+                Some(locked) => (locked.version, locked.checksum),
+                None => ("-".to_string(), package.checksum),
+            };
+            [
+                package.workspace.unwrap_or_else(|| "-".to_string()),
+                package.package_name,
+                dependency,
+                kind,
+                target,
+                version,
+                locked,
+                source,
+                checksum.unwrap_or_else(|| "-".to_string()),
+                features,
+                package.path,
+            ]
+        })
+        .collect()
+}
+
+/// The `[target.<key>]` of a dependency, followed by the `--target` triples it applies to
+/// unless it applies to all of them.
+fn target_cell(target: Option<String>, applies: &[TargetMatch]) -> String {
+    let target = target.unwrap_or_else(|| "-".to_string());
+    if applies.is_empty() {
+        return target;
+    }
+    let matching: Vec<_> = applies
+        .iter()
+        .filter(|target| target.applies)
+        .map(|target| target.triple.as_str())
+        .collect();
+    match matching.as_slice() {
+        [] => format!("{target} (applies to none)"),
+        _ if matching.len() == applies.len() => target,
+        _ => format!("{target} (applies to {})", matching.join(", ")),
+    }
+}
+
+pub fn print_chains(chains: Vec<DependencyChain>, format: &PrintFormat) {
+    match format {
+        PrintFormat::Table => {
+            let rows = chains
+                .into_iter()
+                .map(|chain| {
+                    [
+                        chain.package_name,
+                        chain.dep_name,
+                        chain.version,
+                        chain.chain.join(" -> "),
+                        chain.path,
+                    ]
+                })
+                .collect();
+            print_table(&CHAIN_HEADERS, rows);
+        }
+        PrintFormat::Json => {
+            let mut mapped: HashMap<String, Vec<ChainInfo>> = HashMap::new();
+            chains.into_iter().for_each(|chain| {
+                mapped
+                    .entry(chain.package_name.clone())
+                    .or_default()
+                    .push(ChainInfo::from(chain));
+            });
+            print_json(mapped);
+        }
+        PrintFormat::Ndjson => {
+            print_ndjson_lines(chains.into_iter().map(|chain| NdjsonChain {
+                package: chain.package_name.clone(),
+                info: ChainInfo::from(chain),
+            }));
+        }
+    }
+}
+
+pub fn print_duplicates(mut duplicates: Vec<Duplicate>, format: &PrintFormat, group_by: GroupBy) {
+    sort_duplicates(&mut duplicates, group_by);
+
+    match (format, group_by) {
+        (PrintFormat::Table, GroupBy::Project) => {
+            let rows = duplicates
+                .into_iter()
+                .map(|duplicate| {
+                    [
+                        duplicate.package_name,
+                        duplicate.crate_name,
+                        duplicate.versions.join(", "),
+                        duplicate.path,
+                    ]
+                })
+                .collect();
+            print_table(&PROJECT_DUPLICATE_HEADERS, rows);
+        }
+        (PrintFormat::Table, GroupBy::Crate) => {
+            let rows = duplicates
+                .into_iter()
+                .map(|duplicate| {
+                    [
+                        duplicate.crate_name,
+                        duplicate.versions.join(", "),
+                        duplicate.package_name,
+                        duplicate.path,
+                    ]
+                })
+                .collect();
+            print_table(&CRATE_DUPLICATE_HEADERS, rows);
+        }
+        (PrintFormat::Json, GroupBy::Project) => {
+            let mut mapped: HashMap<String, Vec<ProjectDuplicate>> = HashMap::new();
+            duplicates.into_iter().for_each(|duplicate| {
+                mapped
+                    .entry(duplicate.package_name.clone())
+                    .or_default()
+                    .push(ProjectDuplicate::from(duplicate));
+            });
+            print_json(mapped);
+        }
+        (PrintFormat::Json, GroupBy::Crate) => {
+            let mut mapped: HashMap<String, Vec<CrateDuplicate>> = HashMap::new();
+            duplicates.into_iter().for_each(|duplicate| {
+                mapped
+                    .entry(duplicate.crate_name.clone())
+                    .or_default()
+                    .push(CrateDuplicate::from(duplicate));
+            });
+            print_json(mapped);
+        }
+        (PrintFormat::Ndjson, _) => {
+            print_ndjson_lines(duplicates.into_iter().map(|duplicate| NdjsonDuplicate {
+                package: duplicate.package_name.clone(),
+                info: ProjectDuplicate::from(duplicate),
+            }));
+        }
+    }
+}
+
+/// Orders duplicates so that the entries of a group follow each other, projects being
+/// already sorted by path.
+fn sort_duplicates(duplicates: &mut [Duplicate], group_by: GroupBy) {
+    if group_by == GroupBy::Crate {
+        duplicates.sort_by(|a, b| a.crate_name.cmp(&b.crate_name).then(a.path.cmp(&b.path)));
+    }
+}
+
+pub fn print_stale(stale: Vec<StaleLock>, format: &PrintFormat) {
+    match format {
+        PrintFormat::Table => {
+            let rows = stale
+                .into_iter()
+                .map(|stale| {
+                    [
+                        stale.package_name,
+                        stale.dep_name,
+                        stale.requirement,
+                        stale.locked_version,
+                        stale.manifest,
+                        stale.lock,
+                    ]
+                })
+                .collect();
+            print_table(&STALE_HEADERS, rows);
+        }
+        PrintFormat::Json => {
+            let mut mapped: HashMap<String, Vec<StaleInfo>> = HashMap::new();
+            stale.into_iter().for_each(|stale| {
+                mapped
+                    .entry(stale.package_name.clone())
+                    .or_default()
+                    .push(StaleInfo::from(stale));
+            });
+            print_json(mapped);
+        }
+        PrintFormat::Ndjson => {
+            print_ndjson_lines(stale.into_iter().map(|stale| NdjsonStale {
+                package: stale.package_name.clone(),
+                info: StaleInfo::from(stale),
+            }));
+        }
+    }
+}
+
+pub fn print_patches(patches: Vec<Patch>, format: &PrintFormat) {
+    match format {
+        PrintFormat::Table => {
+            let rows = patches
+                .into_iter()
+                .map(|patch| {
+                    let overridden = patch.overridden;
+                    [
+                        patch.package_name,
+                        overridden.crate_name,
+                        overridden.kind.to_string(),
+                        overridden
+                            .patched
+                            .or(overridden.version)
+                            .unwrap_or_else(|| "-".to_string()),
+                        overridden
+                            .source
+                            .map_or_else(|| "-".to_string(), |source| source.to_string()),
+                        overridden.path,
+                    ]
+                })
+                .collect();
+            print_table(&PATCH_HEADERS, rows);
+        }
+        PrintFormat::Json => {
+            let mut mapped: HashMap<String, Vec<Override>> = HashMap::new();
+            patches.into_iter().for_each(|patch| {
+                mapped
+                    .entry(patch.package_name)
+                    .or_default()
+                    .push(patch.overridden);
             });
             print_json(mapped);
         }
+        PrintFormat::Ndjson => {
+            print_ndjson_lines(patches.into_iter().map(|patch| NdjsonPatch {
+                package: patch.package_name,
+                info: patch.overridden,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{sort_duplicates, target_cell};
+    use crate::{cli::GroupBy, duplicates::Duplicate, parser::cfg::TargetMatch};
+
+    #[test]
+    fn render_target_cells() {
+        let applies = |flags: &[bool]| -> Vec<TargetMatch> {
+            ["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc"]
+                .iter()
+                .zip(flags)
+                .map(|(triple, &applies)| TargetMatch {
+                    triple: triple.to_string(),
+                    applies,
+                })
+                .collect()
+        };
+        let unix = || Some("cfg(unix)".to_string());
+
+        assert_eq!(target_cell(unix(), &[]), "cfg(unix)");
+        assert_eq!(target_cell(None, &applies(&[true, true])), "-");
+        assert_eq!(
+            target_cell(unix(), &applies(&[true, false])),
+            "cfg(unix) (applies to x86_64-unknown-linux-gnu)"
+        );
+        assert_eq!(
+            target_cell(unix(), &applies(&[false, false])),
+            "cfg(unix) (applies to none)"
+        );
+    }
+
+    #[test]
+    fn group_duplicates() {
+        let duplicate = |crate_name: &str, path: &str| Duplicate {
+            package_name: "-".to_string(),
+            crate_name: crate_name.to_string(),
+            versions: vec!["1.0.0".to_string(), "2.0.0".to_string()],
+            path: path.to_string(),
+        };
+        let found = || {
+            vec![
+                duplicate("syn", "a/Cargo.lock"),
+                duplicate("toml", "a/Cargo.lock"),
+                duplicate("syn", "b/Cargo.lock"),
+            ]
+        };
+        let order = |group_by| {
+            let mut duplicates = found();
+            sort_duplicates(&mut duplicates, group_by);
+            duplicates
+                .into_iter()
+                .map(|duplicate| format!("{} {}", duplicate.crate_name, duplicate.path))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            order(GroupBy::Project),
+            ["syn a/Cargo.lock", "toml a/Cargo.lock", "syn b/Cargo.lock"]
+        );
+        assert_eq!(
+            order(GroupBy::Crate),
+            ["syn a/Cargo.lock", "syn b/Cargo.lock", "toml a/Cargo.lock"]
+        );
     }
 }
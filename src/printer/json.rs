@@ -1,13 +1,147 @@
+use crate::{
+    cargo_config::Registry,
+    duplicates::Duplicate,
+    parser::{
+        cfg::TargetMatch,
+        data::{DependencyKind, Locked, OutputRow},
+        overrides::Override,
+        source::Source,
+    },
+    stale::StaleLock,
+    why::DependencyChain,
+};
 use hashbrown::HashMap;
 use serde::Serialize;
 
 #[derive(Serialize)]
 pub struct DepInfo {
+    pub dependency: String,
+    /// The dependency table the entry came from, `null` for Cargo.lock entries.
+    pub kind: Option<DependencyKind>,
+    pub target: Option<String>,
     pub version: String,
     pub path: String,
+    pub inherited: bool,
+    pub alias: Option<String>,
+    pub source: Option<Source>,
+    pub checksum: Option<String>,
+    /// Whether the version could not be compared to the semver filter.
+    pub unevaluated: bool,
+    /// The package the requirement was locked to, for manifest entries of projects
+    /// whose Cargo.lock was read.
+    pub locked: Option<Locked>,
+    pub features: Vec<String>,
+    /// `null` for Cargo.lock entries.
+    pub default_features: Option<bool>,
+    pub optional: bool,
+    pub workspace: Option<String>,
+    pub overridden: Option<Override>,
+    pub registry: Option<Registry>,
+    /// Whether the dependency applies to each target given with `--target`.
+    pub applies: Vec<TargetMatch>,
 }
 
-pub fn print_json(rows: HashMap<String, Vec<DepInfo>>) {
+impl From<OutputRow> for DepInfo {
+    fn from(row: OutputRow) -> Self {
+        DepInfo {
+            dependency: row.dep_name,
+            kind: row.kind,
+            target: row.target,
+            version: row.dep_version,
+            path: row.path,
+            inherited: row.inherited,
+            alias: row.alias,
+            source: row.source,
+            checksum: row.checksum,
+            unevaluated: row.unevaluated,
+            locked: row.locked,
+            features: row.features,
+            default_features: row.default_features,
+            optional: row.optional,
+            workspace: row.workspace,
+            overridden: row.overridden,
+            registry: row.registry,
+            applies: row.applies,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ChainInfo {
+    pub dependency: String,
+    pub version: String,
+    pub chain: Vec<String>,
+    pub path: String,
+}
+
+impl From<DependencyChain> for ChainInfo {
+    fn from(chain: DependencyChain) -> Self {
+        ChainInfo {
+            dependency: chain.dep_name,
+            version: chain.version,
+            chain: chain.chain,
+            path: chain.path,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProjectDuplicate {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub versions: Vec<String>,
+    pub path: String,
+}
+
+impl From<Duplicate> for ProjectDuplicate {
+    fn from(duplicate: Duplicate) -> Self {
+        ProjectDuplicate {
+            crate_name: duplicate.crate_name,
+            versions: duplicate.versions,
+            path: duplicate.path,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CrateDuplicate {
+    pub package: String,
+    pub versions: Vec<String>,
+    pub path: String,
+}
+
+impl From<Duplicate> for CrateDuplicate {
+    fn from(duplicate: Duplicate) -> Self {
+        CrateDuplicate {
+            package: duplicate.package_name,
+            versions: duplicate.versions,
+            path: duplicate.path,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct StaleInfo {
+    pub dependency: String,
+    pub requirement: String,
+    pub locked: String,
+    pub manifest: String,
+    pub lockfile: String,
+}
+
+impl From<StaleLock> for StaleInfo {
+    fn from(stale: StaleLock) -> Self {
+        StaleInfo {
+            dependency: stale.dep_name,
+            requirement: stale.requirement,
+            locked: stale.locked_version,
+            manifest: stale.manifest,
+            lockfile: stale.lock,
+        }
+    }
+}
+
+pub fn print_json<T: Serialize>(rows: HashMap<String, Vec<T>>) {
     match serde_json::to_string_pretty(&rows) {
         Ok(json) => println!("{}", json),
         Err(e) => eprintln!("Error serializing to JSON: {}", e),
@@ -0,0 +1,115 @@
+use super::json::{ChainInfo, DepInfo, ProjectDuplicate, StaleInfo};
+use crate::parser::{data::OutputRow, overrides::Override};
+use serde::Serialize;
+use std::io::{self, ErrorKind, Write};
+
+#[derive(Serialize)]
+pub struct NdjsonRow {
+    pub package: String,
+    #[serde(flatten)]
+    pub info: DepInfo,
+}
+
+#[derive(Serialize)]
+pub struct NdjsonChain {
+    pub package: String,
+    #[serde(flatten)]
+    pub info: ChainInfo,
+}
+
+#[derive(Serialize)]
+pub struct NdjsonDuplicate {
+    pub package: String,
+    #[serde(flatten)]
+    pub info: ProjectDuplicate,
+}
+
+#[derive(Serialize)]
+pub struct NdjsonStale {
+    pub package: String,
+    #[serde(flatten)]
+    pub info: StaleInfo,
+}
+
+#[derive(Serialize)]
+pub struct NdjsonPatch {
+    pub package: String,
+    #[serde(flatten)]
+    pub info: Override,
+}
+
+/// Prints one row per line, returning whether stdout is still open.
+pub fn print_ndjson(rows: Vec<OutputRow>) -> bool {
+    print_ndjson_lines(rows.into_iter().map(|row| NdjsonRow {
+        package: row.package_name.clone(),
+        info: DepInfo::from(row),
+    }))
+}
+
+/// Prints one JSON document per line. Returns `false` once stdout can no longer be written
+/// to, as happens when it is piped into `head`, without reporting a closed pipe.
+pub fn print_ndjson_lines<T: Serialize>(lines: impl Iterator<Item = T>) -> bool {
+    match write_ndjson_lines(io::stdout().lock(), lines) {
+        Ok(()) => true,
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => false,
+        Err(e) => {
+            eprintln!("Error writing output: {}", e);
+            false
+        }
+    }
+}
+
+fn write_ndjson_lines<T: Serialize>(
+    mut out: impl Write,
+    lines: impl Iterator<Item = T>,
+) -> io::Result<()> {
+    for line in lines {
+        match serde_json::to_string(&line) {
+            Ok(json) => writeln!(out, "{}", json)?,
+            Err(e) => eprintln!("Error serializing to JSON: {}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::write_ndjson_lines;
+    use std::io::{self, ErrorKind, Write};
+
+    /// Accepts `open` writes, then fails as a pipe whose reader has exited.
+    struct Pipe {
+        open: usize,
+        written: Vec<u8>,
+    }
+
+    impl Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.open == 0 {
+                return Err(ErrorKind::BrokenPipe.into());
+            }
+            self.open -= 1;
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn stop_at_a_closed_pipe() {
+        let mut pipe = Pipe {
+            open: 2,
+            written: Vec::new(),
+        };
+        let mut lines = [1, 2, 3].into_iter();
+
+        let result = write_ndjson_lines(&mut pipe, lines.by_ref());
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::BrokenPipe);
+        assert_eq!(pipe.written, b"1\n");
+        assert_eq!(lines.next(), Some(3));
+    }
+}
@@ -1,13 +1,38 @@
-pub type Row = [String; 3];
-pub static HEADERS: [&str; 3] = ["PACKAGE", "VERSION", "PATH"];
+pub type Row = [String; 11];
+pub static HEADERS: [&str; 11] = [
+    "WORKSPACE",
+    "PACKAGE",
+    "DEPENDENCY",
+    "KIND",
+    "TARGET",
+    "VERSION",
+    "LOCKED",
+    "SOURCE",
+    "CHECKSUM",
+    "FEATURES",
+    "PATH",
+];
 
-pub fn print_table(rows: Vec<Row>) {
-    let max_by_col = max_by_column(&HEADERS, &rows);
+pub static CHAIN_HEADERS: [&str; 5] = ["PACKAGE", "DEPENDENCY", "VERSION", "CHAIN", "PATH"];
+pub static PROJECT_DUPLICATE_HEADERS: [&str; 4] = ["PACKAGE", "CRATE", "VERSIONS", "PATH"];
+pub static STALE_HEADERS: [&str; 6] = [
+    "PACKAGE",
+    "DEPENDENCY",
+    "REQUIREMENT",
+    "LOCKED",
+    "MANIFEST",
+    "LOCKFILE",
+];
+pub static PATCH_HEADERS: [&str; 6] = ["PACKAGE", "CRATE", "OVERRIDE", "PATCHED", "SOURCE", "PATH"];
+pub static CRATE_DUPLICATE_HEADERS: [&str; 4] = ["CRATE", "VERSIONS", "PACKAGE", "PATH"];
+
+pub fn print_table<const N: usize>(headers: &[&str; N], rows: Vec<[String; N]>) {
+    let max_by_col = max_by_column(headers, &rows);
 
     let margin = 4;
 
     // Headers
-    for (i, header) in HEADERS.iter().enumerate() {
+    for (i, header) in headers.iter().enumerate() {
         print!("{:<width$}", header, width = max_by_col[i] + margin);
     }
     println!();
@@ -21,7 +46,7 @@ pub fn print_table(rows: Vec<Row>) {
     }
 }
 
-fn max_by_column(headers: &[&str], rows: &[Row]) -> Vec<usize> {
+fn max_by_column<const N: usize>(headers: &[&str; N], rows: &[[String; N]]) -> Vec<usize> {
     let mut max = vec![0; headers.len()];
 
     for (i, header) in headers.iter().enumerate() {
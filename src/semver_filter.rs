@@ -1,18 +1,261 @@
-use crate::parser::data::OutputRow;
-use semver::{Version, VersionReq};
+use crate::{cli::FilterMode, parser::data::OutputRow};
+use semver::{BuildMetadata, Comparator, Op, Prerelease, Version, VersionReq};
+use std::cmp::Ordering;
+
+/// Keeps the rows whose version satisfies the filter. Manifest rows hold requirements,
+/// compared to the filter according to `mode`, while Cargo.lock rows hold exact versions.
+/// Rows whose version cannot be evaluated, such as git dependencies without a version,
+/// are kept and flagged as unevaluated.
+pub fn filter(
+    filter: Option<&VersionReq>,
+    mode: FilterMode,
+    data: Vec<OutputRow>,
+) -> Vec<OutputRow> {
+    let Some(filter) = filter else {
+        return data;
+    };
+    let filter = VersionRange::from_req(filter);
 
-pub fn filter(filter: Option<VersionReq>, data: Vec<OutputRow>) -> Vec<OutputRow> {
     data.into_iter()
-        .filter(|row| {
-            if let Some(filter) = &filter {
-                if let Ok(dep_version) = Version::parse(&row.dep_version) {
-                    filter.matches(&dep_version)
-                } else {
-                    false
-                }
-            } else {
-                true
+        .filter_map(|mut row| match row_range(&row) {
+            Some(range) if mode.accepts(&filter, &range) => Some(row),
+            Some(_) => None,
+            None => {
+                row.unevaluated = true;
+                Some(row)
             }
         })
         .collect()
 }
+
+/// The versions a row may resolve to, `None` when its version cannot be parsed.
+fn row_range(row: &OutputRow) -> Option<VersionRange> {
+    if row.kind.is_none() {
+        let version = Version::parse(&row.dep_version).ok()?;
+        return Some(VersionRange::exact(version));
+    }
+    VersionReq::parse(&row.dep_version)
+        .ok()
+        .map(|req| VersionRange::from_req(&req))
+}
+
+impl FilterMode {
+    fn accepts(&self, filter: &VersionRange, range: &VersionRange) -> bool {
+        match self {
+            FilterMode::Intersects => !filter.intersect(range).is_empty(),
+            FilterMode::Subset => filter.contains(range),
+        }
+    }
+}
+
+/// A bound of a [`VersionRange`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bound {
+    version: Version,
+    inclusive: bool,
+}
+
+/// The interval of versions matched by a requirement, every comparator of a requirement
+/// narrowing the same interval. Pre-releases are ordered as semver orders them, which is
+/// looser than cargo, only matching them when the requirement names one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct VersionRange {
+    /// `None` when unbounded.
+    lower: Option<Bound>,
+    /// `None` when unbounded.
+    upper: Option<Bound>,
+}
+
+impl VersionRange {
+    fn exact(version: Version) -> Self {
+        VersionRange {
+            lower: Some(Bound {
+                version: version.clone(),
+                inclusive: true,
+            }),
+            upper: Some(Bound {
+                version,
+                inclusive: true,
+            }),
+        }
+    }
+
+    fn from_req(req: &VersionReq) -> Self {
+        req.comparators
+            .iter()
+            .map(VersionRange::from_comparator)
+            .fold(VersionRange::default(), |range, next| {
+                range.intersect(&next)
+            })
+    }
+
+    fn from_comparator(comparator: &Comparator) -> Self {
+        let major = comparator.major;
+        let (minor, patch) = (comparator.minor, comparator.patch);
+        let version = Version {
+            major,
+            minor: minor.unwrap_or(0),
+            patch: patch.unwrap_or(0),
+            pre: comparator.pre.clone(),
+            build: BuildMetadata::EMPTY,
+        };
+        // The first release past the ones the comparator names, e.g. 1.3.0 for `1.2`.
+        let next = match (minor, patch) {
+            (None, _) => Version::new(major + 1, 0, 0),
+            (Some(minor), None) => Version::new(major, minor + 1, 0),
+            (Some(minor), Some(patch)) => Version::new(major, minor, patch + 1),
+        };
+        let at_least = |inclusive| Bound {
+            version: version.clone(),
+            inclusive,
+        };
+
+        match comparator.op {
+            Op::Exact | Op::Wildcard if patch.is_some() => VersionRange::exact(version),
+            Op::Exact | Op::Wildcard => VersionRange::between(at_least(true), exclusive(next)),
+            Op::Greater if patch.is_some() => VersionRange::above(at_least(false)),
+            Op::Greater => VersionRange::above(Bound {
+                version: next,
+                inclusive: true,
+            }),
+            Op::GreaterEq => VersionRange::above(at_least(true)),
+            Op::Less => VersionRange::below(exclusive(version)),
+            Op::LessEq if patch.is_some() => VersionRange::below(at_least(true)),
+            Op::LessEq => VersionRange::below(exclusive(next)),
+            Op::Tilde => {
+                let upper = match minor {
+                    Some(minor) => Version::new(major, minor + 1, 0),
+                    None => Version::new(major + 1, 0, 0),
+                };
+                VersionRange::between(at_least(true), exclusive(upper))
+            }
+            Op::Caret => {
+                let upper = match (major, minor, patch) {
+                    (0, Some(0), Some(patch)) => Version::new(0, 0, patch + 1),
+                    (0, Some(minor), _) => Version::new(0, minor + 1, 0),
+                    _ => Version::new(major + 1, 0, 0),
+                };
+                VersionRange::between(at_least(true), exclusive(upper))
+            }
+            _ => VersionRange::default(),
+        }
+    }
+
+    fn between(lower: Bound, upper: Bound) -> Self {
+        VersionRange {
+            lower: Some(lower),
+            upper: Some(upper),
+        }
+    }
+
+    fn above(lower: Bound) -> Self {
+        VersionRange {
+            lower: Some(lower),
+            upper: None,
+        }
+    }
+
+    fn below(upper: Bound) -> Self {
+        VersionRange {
+            lower: None,
+            upper: Some(upper),
+        }
+    }
+
+    fn intersect(&self, other: &VersionRange) -> VersionRange {
+        let lower = match (&self.lower, &other.lower) {
+            (Some(a), Some(b)) if compare_lower(a, b) == Ordering::Less => Some(b.clone()),
+            (Some(a), _) => Some(a.clone()),
+            (None, b) => b.clone(),
+        };
+        let upper = match (&self.upper, &other.upper) {
+            (Some(a), Some(b)) if compare_upper(a, b) == Ordering::Greater => Some(b.clone()),
+            (Some(a), _) => Some(a.clone()),
+            (None, b) => b.clone(),
+        };
+        VersionRange { lower, upper }
+    }
+
+    fn is_empty(&self) -> bool {
+        match (&self.lower, &self.upper) {
+            (Some(lower), Some(upper)) => match lower.version.cmp(&upper.version) {
+                Ordering::Greater => true,
+                Ordering::Equal => !(lower.inclusive && upper.inclusive),
+                Ordering::Less => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether every version of `other` is part of this range.
+    fn contains(&self, other: &VersionRange) -> bool {
+        if other.is_empty() {
+            return true;
+        }
+        let lower = match (&self.lower, &other.lower) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(a), Some(b)) => compare_lower(a, b) != Ordering::Greater,
+        };
+        let upper = match (&self.upper, &other.upper) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(a), Some(b)) => compare_upper(a, b) != Ordering::Less,
+        };
+        lower && upper
+    }
+}
+
+/// Orders lower bounds from the loosest to the tightest.
+fn compare_lower(a: &Bound, b: &Bound) -> Ordering {
+    a.version
+        .cmp(&b.version)
+        .then_with(|| b.inclusive.cmp(&a.inclusive))
+}
+
+/// Orders upper bounds from the tightest to the loosest.
+fn compare_upper(a: &Bound, b: &Bound) -> Ordering {
+    a.version
+        .cmp(&b.version)
+        .then_with(|| a.inclusive.cmp(&b.inclusive))
+}
+
+/// An exclusive upper bound, placed before the pre-releases of the given version
+/// since `<2.0.0` does not match `2.0.0-alpha` either.
+fn exclusive(mut version: Version) -> Bound {
+    if version.pre.is_empty() {
+        version.pre = Prerelease::new("0").unwrap_or(Prerelease::EMPTY);
+    }
+    Bound {
+        version,
+        inclusive: false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::VersionRange;
+    use crate::cli::FilterMode;
+    use semver::VersionReq;
+
+    fn accepts(mode: FilterMode, filter: &str, requirement: &str) -> bool {
+        let filter = VersionRange::from_req(&VersionReq::parse(filter).unwrap());
+        let range = VersionRange::from_req(&VersionReq::parse(requirement).unwrap());
+        mode.accepts(&filter, &range)
+    }
+
+    #[test]
+    fn compare_requirements_to_filter() {
+        assert!(accepts(FilterMode::Intersects, ">=1.0.100", "1.0"));
+        assert!(!accepts(FilterMode::Subset, ">=1.0.100", "1.0"));
+        assert!(accepts(FilterMode::Subset, ">=1.0.0, <2", "^1.2"));
+        assert!(accepts(FilterMode::Subset, "1", ">=1.2, <2"));
+        assert!(!accepts(FilterMode::Intersects, ">=2", "^0.4"));
+        assert!(!accepts(FilterMode::Intersects, "<1.0.0", "=1.0.0"));
+        assert!(accepts(FilterMode::Intersects, "<=1.0", "~1.0.5"));
+        assert!(!accepts(FilterMode::Intersects, ">0.2", "0.2.*"));
+        assert!(accepts(FilterMode::Subset, "^0.0.3", "=0.0.3"));
+        assert!(!accepts(FilterMode::Subset, "^1", "*"));
+        assert!(accepts(FilterMode::Intersects, "^1", "*"));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{cli::Args, error::MoleError};
+use semver::{Version, VersionReq};
+
+/// A manifest requirement that the version locked in the project's Cargo.lock does not
+/// satisfy, typically after a hand-edited manifest or a bad merge.
+#[derive(Debug, Clone)]
+pub struct StaleLock {
+    pub package_name: String,
+    pub dep_name: String,
+    pub requirement: String,
+    pub locked_version: String,
+    /// The Cargo.toml declaring the requirement.
+    pub manifest: String,
+    /// The Cargo.lock holding the locked version.
+    pub lock: String,
+}
+
+/// Finds the dependencies matching `args.name` and `args.regex`, or every dependency when
+/// none is given, whose locked version does not satisfy their requirement. The dependencies
+/// are searched as in `search` mode, so `args.filter`, `args.source` and `args.feature`
+/// narrow them as well.
+pub fn stale_locks(args: &Args) -> Result<Vec<StaleLock>, MoleError> {
+    Ok(crate::search(args)?
+        .into_iter()
+        .filter_map(|row| {
+            let locked = row.locked?;
+            let requirement = VersionReq::parse(&row.dep_version).ok()?;
+            let version = Version::parse(&locked.version).ok()?;
+            (!requirement.matches(&version)).then_some(StaleLock {
+                package_name: row.package_name,
+                dep_name: row.dep_name,
+                requirement: row.dep_version,
+                locked_version: locked.version,
+                manifest: row.path,
+                lock: locked.path,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::stale_locks;
+    use crate::{cli::Args, fixture, search};
+    use clap::Parser;
+
+    const LOCK: &str = r#"
+    [[package]]
+    name = "log"
+    version = "0.4.22"
+    source = "registry+https://github.com/rust-lang/crates.io-index"
+
+    [[package]]
+    name = "serde"
+    version = "1.0.210"
+    source = "registry+https://github.com/rust-lang/crates.io-index"
+    "#;
+
+    #[test]
+    fn report_requirements_the_lock_does_not_satisfy() {
+        let dir = fixture::dir(&[
+            (
+                "app/Cargo.toml",
+                "[package]\nname = \"app\"\n\n[dependencies]\nserde = \"1.0.200\"\n",
+            ),
+            ("app/Cargo.lock", LOCK),
+            ("ws/Cargo.toml", "[workspace]\nmembers = [\"member\"]\n"),
+            ("ws/Cargo.lock", LOCK),
+            (
+                "ws/member/Cargo.toml",
+                "[package]\nname = \"member\"\n\n[dependencies]\nlog = \"0.4.25\"\nserde = \"1\"\n",
+            ),
+        ]);
+        let path = dir.path().to_str().unwrap();
+
+        let found: Vec<_> = stale_locks(&Args::parse_from(["mole", "-p", path, "-m", "stale"]))
+            .unwrap()
+            .into_iter()
+            .map(|stale| {
+                let lock = stale.lock.strip_prefix(path).unwrap().to_string();
+                let manifest = stale.manifest.strip_prefix(path).unwrap().to_string();
+                [
+                    stale.package_name,
+                    stale.dep_name,
+                    stale.requirement,
+                    stale.locked_version,
+                    manifest,
+                    lock,
+                ]
+            })
+            .collect();
+
+        assert_eq!(
+            found,
+            [[
+                "member",
+                "log",
+                "0.4.25",
+                "0.4.22",
+                "/ws/member/Cargo.toml",
+                "/ws/Cargo.lock"
+            ]]
+        );
+        // The satisfied requirements were paired with a locked version rather than skipped.
+        let serde = search(&Args::parse_from(["mole", "-p", path, "-d", "-n", "serde"])).unwrap();
+        let locked: Vec<_> = serde
+            .iter()
+            .filter(|row| row.kind.is_some())
+            .map(|row| row.locked.as_ref().map(|locked| locked.version.as_str()))
+            .collect();
+        assert_eq!(locked, [Some("1.0.210"), Some("1.0.210")]);
+    }
+}
@@ -0,0 +1,31 @@
+use crate::error::MoleError;
+use hashbrown::HashMap;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, OnceLock},
+    thread,
+};
+
+/// Pools built so far, keyed by their number of threads.
+static POOLS: OnceLock<Mutex<HashMap<usize, Arc<ThreadPool>>>> = OnceLock::new();
+
+/// Returns a thread pool with the given number of threads, defaulting to the available
+/// parallelism. Pools are built once and reused by later calls asking for the same size.
+pub fn get(threads: Option<NonZeroUsize>) -> Result<Arc<ThreadPool>, MoleError> {
+    let threads = threads
+        .or_else(|| thread::available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get);
+
+    let mut pools = POOLS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if let Some(pool) = pools.get(&threads) {
+        return Ok(pool.clone());
+    }
+    let pool = Arc::new(ThreadPoolBuilder::new().num_threads(threads).build()?);
+    pools.insert(threads, pool.clone());
+    Ok(pool)
+}
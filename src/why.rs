@@ -0,0 +1,89 @@
+use crate::{
+    cli::Args,
+    error::MoleError,
+    file_explorer,
+    matcher::DependencyMatcher,
+    parser::{data::CLockFile, lock::LockGraph},
+};
+use std::{fs, path::Path};
+
+/// A chain of locked dependencies leading from a local crate to a version of the target.
+#[derive(Debug, Clone)]
+pub struct DependencyChain {
+    /// The local crate the chain starts from.
+    pub package_name: String,
+    /// The matched crate the chain ends at.
+    pub dep_name: String,
+    /// The locked version of the target the chain ends at.
+    pub version: String,
+    /// Every package of the chain as `name version`, from the local crate to the target.
+    pub chain: Vec<String>,
+    /// The Cargo.lock the chain was found in.
+    pub path: String,
+}
+
+/// Finds, in every Cargo.lock under `args.path`, how each local crate depends on the crates
+/// matching `args.name` and `args.regex`.
+/// Only the shortest chain to each locked version is kept unless `args.all_paths` is set.
+pub fn why(args: &Args) -> Result<Vec<DependencyChain>, MoleError> {
+    let matcher = args.required_matcher()?;
+    let mut found = Vec::new();
+
+    for (_, package) in file_explorer::explore(args)? {
+        if let Some(ref lock) = package.clock {
+            found.extend(lock_chains(lock, &matcher, args.all_paths)?);
+        }
+    }
+    found.sort_by(|a, b| {
+        a.path
+            .cmp(&b.path)
+            .then(a.package_name.cmp(&b.package_name))
+            .then(a.dep_name.cmp(&b.dep_name))
+            .then(a.version.cmp(&b.version))
+            .then(a.chain.len().cmp(&b.chain.len()))
+    });
+    Ok(found)
+}
+
+fn lock_chains(
+    path: &Path,
+    matcher: &DependencyMatcher,
+    all_paths: bool,
+) -> Result<Vec<DependencyChain>, MoleError> {
+    let contents = fs::read_to_string(path)?;
+    let lock: CLockFile = match toml::from_str(&contents) {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("Unparseable file: {:?} {e}", path);
+            return Ok(Vec::new());
+        }
+    };
+    let graph = LockGraph::new(lock);
+    let mut res = Vec::new();
+
+    for target in graph.find(|name| matcher.matches(name)) {
+        for root in graph.roots() {
+            let paths = if all_paths {
+                graph.all_paths(root, target)
+            } else {
+                graph.shortest_path(root, target).into_iter().collect()
+            };
+            res.extend(paths.into_iter().map(|chain| {
+                DependencyChain {
+                    package_name: graph.packages[root].name.clone(),
+                    dep_name: graph.packages[target].name.clone(),
+                    version: graph.packages[target].version.clone(),
+                    chain: chain
+                        .into_iter()
+                        .map(|id| {
+                            let package = &graph.packages[id];
+                            format!("{} {}", package.name, package.version)
+                        })
+                        .collect(),
+                    path: path.to_string_lossy().to_string(),
+                }
+            }));
+        }
+    }
+    Ok(res)
+}